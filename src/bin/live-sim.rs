@@ -0,0 +1,390 @@
+//! Headless simulation runner.
+//! Builds the world from `bac_settings.toml` (or `--settings`), runs it as fast as possible and prints a summary.
+//! `--preset NAME` puts a built-in preset under the settings file.
+//! `LIVE_<KEY>` environment variables and then `--set key=value` override single settings,
+//! e.g. `--set photosynth_rate=0.05 --set radius_range=6..18`.
+//!
+//! Optionally records frames with the software renderer.
+//! `--load` resumes a world snapshot (its settings and size are used), `--save` writes one after the run.
+//! `--csv` writes the living bacteries with their ids, lineage and gens after the run.
+//! `--newick` writes their ancestry tree, `--newick-lengths` chooses branch lengths in generations or simulated seconds.
+//! `--species` writes every species seen with its lifetime, size and representative gens.
+//! `--autosave` keeps periodic snapshots as set by `autosave_interval_sec` and `autosave_keep`.
+//! Edits of the settings file apply while the world runs, unless it is resumed with `--load`.
+//! SIGINT/SIGTERM stop the run early, the outputs and the final snapshot are still written.
+//! `--explain KEY` describes one setting, `--print-default-config` prints the default settings file.
+//!
+//! Usage: live-sim [--ticks N | --seconds S | --forever] [--width W] [--height H] [--seed SEED]
+//!                 [--png DIR] [--y4m PATH|-] [--fps N] [--frame-every N] [--frame-size WxH]
+//!                 [--load PATH] [--save PATH] [--autosave DIR] [--settings PATH] [--preset NAME] [--set KEY=VALUE]...
+//!                 [--csv PATH] [--newick PATH] [--newick-lengths generations|time] [--species PATH]
+//!        live-sim --explain KEY | --print-default-config
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::str::FromStr;
+use std::time::Instant;
+
+use live_wallpapers::live::export::{FrameRecorder, PngSequence, Y4mStream};
+use live_wallpapers::live::genome::GENES;
+use live_wallpapers::live::lineage::BranchLength;
+use live_wallpapers::live::presets::{find_preset, Preset, PRESETS};
+use live_wallpapers::live::save_load::{write_csv, write_species_csv, SettingsError, SettingsOverride, SettingsSource, SettingsWatcher};
+use live_wallpapers::live::settings_schema::{default_settings_file, find_setting, NamedEnum, SETTINGS};
+use live_wallpapers::live::simulation::Simulation;
+use live_wallpapers::live::snapshot::{load_snapshot, save_snapshot, Autosave};
+
+const USAGE: &str = "Usage: live-sim [--ticks N | --seconds S | --forever] [--width W] [--height H] [--seed SEED]
+                [--png DIR] [--y4m PATH|-] [--fps N] [--frame-every N] [--frame-size WxH]
+                [--load PATH] [--save PATH] [--autosave DIR] [--settings PATH] [--preset NAME] [--set KEY=VALUE]...
+                [--csv PATH] [--newick PATH] [--newick-lengths generations|time] [--species PATH]
+       live-sim --explain KEY | --print-default-config";
+
+struct Args {
+    ticks: Option<u128>,
+    seconds: Option<f32>,
+    /// Run until interrupted.
+    forever: bool,
+    width: usize,
+    height: usize,
+    seed: Option<u64>,
+    png_dir: Option<PathBuf>,
+    /// `-` is stdout.
+    y4m_path: Option<String>,
+    fps: u32,
+    frame_every: u128,
+    frame_size: Option<(usize, usize)>,
+    load_path: Option<PathBuf>,
+    save_path: Option<PathBuf>,
+    csv_path: Option<PathBuf>,
+    newick_path: Option<PathBuf>,
+    newick_lengths: BranchLength,
+    species_path: Option<PathBuf>,
+    autosave_dir: Option<PathBuf>,
+    settings_path: Option<PathBuf>,
+    preset: Option<&'static Preset>,
+    overrides: Vec<SettingsOverride>,
+}
+
+fn main() {
+    let args = parse_args();
+    let (mut simulation, mut settings_watcher) = match &args.load_path {
+        Some(path) => (load(path, &args), None),
+        None => {
+            let mut source = SettingsSource::new(args.settings_path.as_deref(), args.overrides.clone());
+            source.preset = args.preset;
+            let mut settings = source.load().unwrap_or_else(|errors| settings_failed(&errors));
+            if let Some(seed) = args.seed {
+                settings.seed = seed;
+            }
+            (Simulation::new(args.width, args.height, settings), Some(SettingsWatcher::new(source)))
+        },
+    };
+
+    let ticks = match (args.forever, args.ticks, args.seconds) {
+        (true, _, _) => u128::MAX,
+        (false, Some(ticks), _) => ticks,
+        (false, None, Some(seconds)) => (seconds / simulation.settings().time_step).ceil() as u128,
+        (false, None, None) => 10_000,
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst)) {
+        eprintln!("Can`t set the stop signal handler with error: {}", e);
+    }
+
+    let mut autosave = args.autosave_dir.as_deref().map(|dir| match Autosave::new(dir) {
+        Ok(autosave) => autosave,
+        Err(e) => fail(&format!("Can`t create autosave directory {} with error: {}", dir.display(), e)),
+    });
+    let mut recorders = create_recorders(&args, &simulation);
+    capture(&mut recorders, &simulation);
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        if stop.load(Ordering::SeqCst) {
+            eprintln!("Stopped at tick {}", simulation.frame_num());
+            break;
+        }
+
+        simulation.step();
+        capture(&mut recorders, &simulation);
+        if let Some(Err(e)) = autosave.as_mut().map(|a| a.tick(&simulation)) {
+            eprintln!("Can`t autosave with error: {}", e);
+        }
+        if let Some(watcher) = settings_watcher.as_mut() {
+            watcher.update(&mut simulation);
+        }
+    }
+
+    for recorder in recorders.iter_mut() {
+        if let Err(e) = recorder.finish() {
+            fail(&format!("Can`t finish frames output with error: {}", e));
+        }
+    }
+
+    if let Some(path) = &args.save_path {
+        if let Err(e) = save_snapshot(&simulation, path) {
+            fail(&format!("Can`t save snapshot {} with error: {}", path.display(), e));
+        }
+    }
+    if let Some(path) = &args.csv_path {
+        if let Err(e) = write_csv(simulation.app(), path) {
+            fail(&format!("Can`t write csv {} with error: {}", path.display(), e));
+        }
+    }
+    if let Some(path) = &args.newick_path {
+        let live = simulation.live_data();
+        let tree = live.lineage.newick(&live.living_ids(), args.newick_lengths, simulation.frame_num(), live.settings.time_step);
+        if let Err(e) = std::fs::write(path, tree + "\n") {
+            fail(&format!("Can`t write newick {} with error: {}", path.display(), e));
+        }
+    }
+    if let Some(path) = &args.species_path {
+        if let Err(e) = write_species_csv(simulation.app(), path) {
+            fail(&format!("Can`t write species {} with error: {}", path.display(), e));
+        }
+    }
+    if let Some(Err(e)) = autosave.as_mut().map(|a| a.save(&simulation)) {
+        fail(&format!("Can`t write the final autosave with error: {}", e));
+    }
+
+    // Keep stdout clean when the video goes there.
+    let mut out: Box<dyn Write> = match args.y4m_path.as_deref() {
+        Some("-") => Box::new(io::stderr()),
+        _ => Box::new(io::stdout()),
+    };
+    if let Err(e) = print_summary(&mut out, &simulation, start.elapsed().as_secs_f32()) {
+        fail(&format!("Can`t print summary with error: {}", e));
+    }
+}
+
+fn parse_args() -> Args {
+    let mut result = Args {
+        ticks: None,
+        seconds: None,
+        forever: false,
+        width: 1920,
+        height: 1080,
+        seed: None,
+        png_dir: None,
+        y4m_path: None,
+        fps: 30,
+        frame_every: 100,
+        frame_size: None,
+        load_path: None,
+        save_path: None,
+        csv_path: None,
+        newick_path: None,
+        newick_lengths: BranchLength::Generations,
+        species_path: None,
+        autosave_dir: None,
+        settings_path: None,
+        preset: None,
+        overrides: Vec::new(),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" => result.ticks = Some(value(&arg, args.next())),
+            "--seconds" => result.seconds = Some(value(&arg, args.next())),
+            "--forever" => result.forever = true,
+            "--width" => result.width = positive(&arg, args.next()),
+            "--height" => result.height = positive(&arg, args.next()),
+            "--seed" => result.seed = Some(value(&arg, args.next())),
+            "--png" => result.png_dir = Some(value(&arg, args.next())),
+            "--y4m" => result.y4m_path = Some(value(&arg, args.next())),
+            "--fps" => result.fps = value(&arg, args.next()),
+            "--frame-every" => result.frame_every = value(&arg, args.next()),
+            "--frame-size" => result.frame_size = Some(size(&arg, args.next())),
+            "--load" => result.load_path = Some(value(&arg, args.next())),
+            "--save" => result.save_path = Some(value(&arg, args.next())),
+            "--csv" => result.csv_path = Some(value(&arg, args.next())),
+            "--newick" => result.newick_path = Some(value(&arg, args.next())),
+            "--species" => result.species_path = Some(value(&arg, args.next())),
+            "--newick-lengths" => result.newick_lengths = branch_length(&value::<String>(&arg, args.next())),
+            "--autosave" => result.autosave_dir = Some(value(&arg, args.next())),
+            "--settings" => result.settings_path = Some(value(&arg, args.next())),
+            "--preset" => result.preset = Some(preset(&value::<String>(&arg, args.next()))),
+            "--set" => {
+                let value = value::<String>(&arg, args.next());
+                match SettingsOverride::parse_arg(&value) {
+                    Ok(o) => result.overrides.push(o),
+                    Err(e) => fail(&format!("Invalid value for --set, {}", e)),
+                }
+            },
+            "--explain" => explain(&value::<String>(&arg, args.next())),
+            "--print-default-config" => {
+                print!("{}", default_settings_file());
+                exit(0);
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                exit(0);
+            },
+            _ => fail(&format!("Unknown argument {}", arg)),
+        }
+    }
+
+    result
+}
+
+fn explain(key: &str) -> ! {
+    match find_setting(key) {
+        Some(setting) => {
+            print!("{}", setting.explain());
+            exit(0);
+        },
+        None => {
+            let keys = SETTINGS.iter().map(|s| s.name).collect::<Vec<&str>>();
+            eprintln!("Unknown setting {}, the settings are: {}", key, keys.join(", "));
+            exit(2);
+        },
+    }
+}
+
+fn preset(name: &str) -> &'static Preset {
+    find_preset(name).unwrap_or_else(|| {
+        eprintln!("Unknown preset {}, the presets are:", name);
+        for preset in PRESETS.iter() {
+            eprintln!("    {:<20} {}", preset.name, preset.description);
+        }
+        exit(2);
+    })
+}
+
+fn branch_length(name: &str) -> BranchLength {
+    BranchLength::from_name(name).unwrap_or_else(|| {
+        fail(&format!("Invalid value for --newick-lengths, expected {}", BranchLength::names()))
+    })
+}
+
+fn load(path: &Path, args: &Args) -> Simulation {
+    if args.seed.is_some() {
+        fail("--seed can`t be used with --load, the snapshot keeps its random state");
+    }
+    if args.settings_path.is_some() || args.preset.is_some() || !args.overrides.is_empty() {
+        fail("--settings, --preset and --set can`t be used with --load, the snapshot keeps its settings");
+    }
+
+    match load_snapshot(path) {
+        Ok(simulation) => {
+            eprintln!("Snapshot {} loaded at tick {}", path.display(), simulation.frame_num());
+            simulation
+        },
+        Err(e) => fail(&format!("Can`t load snapshot {} with error: {}", path.display(), e)),
+    }
+}
+
+fn value<T: FromStr>(name: &str, value: Option<String>) -> T {
+    match value.map(|v| v.parse::<T>()) {
+        Some(Ok(v)) => v,
+        Some(Err(_)) => fail(&format!("Invalid value for {}", name)),
+        None => fail(&format!("Missing value for {}", name)),
+    }
+}
+
+fn positive(name: &str, value: Option<String>) -> usize {
+    match value.map(|v| v.parse::<usize>()) {
+        Some(Ok(v)) if v > 0 => v,
+        Some(_) => fail(&format!("Invalid value for {}, expected a positive integer", name)),
+        None => fail(&format!("Missing value for {}", name)),
+    }
+}
+
+/// `WxH`, e.g. `1280x720`.
+fn size(name: &str, value: Option<String>) -> (usize, usize) {
+    let value = value.unwrap_or_else(|| fail(&format!("Missing value for {}", name)));
+    match value.split_once('x').map(|(w, h)| (w.parse::<usize>(), h.parse::<usize>())) {
+        Some((Ok(w), Ok(h))) if w > 0 && h > 0 => (w, h),
+        _ => fail(&format!("Invalid value for {}, expected WxH", name)),
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    eprintln!("{}", USAGE);
+    exit(2);
+}
+
+fn settings_failed(errors: &[SettingsError]) -> ! {
+    for error in errors {
+        eprintln!("{}", error);
+    }
+    exit(2);
+}
+
+fn create_recorders(args: &Args, simulation: &Simulation) -> Vec<FrameRecorder> {
+    let world_size = (simulation.app().width, simulation.app().height);
+    let (width, height) = args.frame_size.unwrap_or(world_size);
+    let mut result = Vec::new();
+
+    if let Some(dir) = &args.png_dir {
+        match PngSequence::new(dir) {
+            Ok(sink) => result.push(FrameRecorder::new(args.frame_every, width, height, Box::new(sink))),
+            Err(e) => fail(&format!("Can`t create png directory {} with error: {}", dir.display(), e)),
+        }
+    }
+
+    match args.y4m_path.as_deref() {
+        Some("-") => {
+            let sink = Y4mStream::new(BufWriter::new(io::stdout()), args.fps);
+            result.push(FrameRecorder::new(args.frame_every, width, height, Box::new(sink)));
+        },
+        Some(path) => match File::create(path) {
+            Ok(file) => {
+                let sink = Y4mStream::new(BufWriter::new(file), args.fps);
+                result.push(FrameRecorder::new(args.frame_every, width, height, Box::new(sink)));
+            },
+            Err(e) => fail(&format!("Can`t create y4m file {} with error: {}", path, e)),
+        },
+        None => {},
+    }
+
+    result
+}
+
+fn capture(recorders: &mut [FrameRecorder], simulation: &Simulation) {
+    for recorder in recorders.iter_mut() {
+        if let Err(e) = recorder.capture(simulation.app()) {
+            fail(&format!("Can`t write frame with error: {}", e));
+        }
+    }
+}
+
+fn print_summary(out: &mut dyn Write, simulation: &Simulation, elapsed_sec: f32) -> io::Result<()> {
+    let live = simulation.live_data();
+    let bac = &live.bacteries;
+    let dead_time = live.settings.dead_time;
+    let alive = bac.into_iter().filter(|i| bac.is_alive(*i, dead_time)).collect::<Vec<usize>>();
+
+    writeln!(out, "Ticks: {} ({:.1} simulated sec, {:.1} real sec)", simulation.frame_num(), simulation.time_sec(), elapsed_sec)?;
+    writeln!(out, "Population: {} / {}", alive.len(), bac.num)?;
+    writeln!(out, "Births: {}", live.births)?;
+    writeln!(out, "Deaths: {}", live.deaths)?;
+    let max_generation = alive.iter().map(|i| bac.generation[*i]).max().unwrap_or(0);
+    writeln!(out, "Generations: up to {}", max_generation)?;
+    let species = &live.species;
+    match species.living().max_by_key(|s| s.size) {
+        Some(largest) => writeln!(out, "Species: {} living (largest #{} with {}), {} extinct",
+                                  species.living().count(), largest.id, largest.size, species.extinct_count())?,
+        None => writeln!(out, "Species: none living, {} extinct", species.extinct_count())?,
+    }
+    writeln!(out, "Organic matter: {:.2}", live.organic.total())?;
+    writeln!(out, "Mean gens:")?;
+    for (info, gen) in GENES.iter().zip(bac.genome.iter()) {
+        let mean = match alive.len() {
+            0 => 0.0,
+            n => alive.iter().map(|i| gen[*i]).sum::<f32>() / n as f32,
+        };
+        writeln!(out, "    {:<20} {:.4}", info.name, mean)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::ptr::null_mut;
+
+use wallpaper_app::drawing::primitives::{open_draw_frame, draw_fullscreen_rect, close_draw_frame, change_solid_brush, draw_circle, revert_brush, create_solid_pen, close_draw_lines};
+use winapi::shared::windef::{HDC, HWND, POINT};
+use winapi::um::wingdi::{Polyline, SetBkMode, SetTextColor, TextOutW, TRANSPARENT};
+use winapi::um::winuser::{RedrawWindow, RDW_INVALIDATE, PAINTSTRUCT, MSG};
+use live_wallpapers::live::app::AppData;
+use live_wallpapers::live::render::{self, Color, Renderer};
+
+
+pub struct GraphicsPipeline<T: Fn(MSG) -> bool> {
+    messages_handler: T,
+}
+
+impl<T: Fn(MSG) -> bool> GraphicsPipeline<T> {
+    pub fn new(messages_handler: T) -> GraphicsPipeline<T> {
+        GraphicsPipeline {
+            messages_handler,
+        }
+    }
+
+    /// Returns true if a window message was handled, otherwise requests a redraw.
+    pub fn step(&self, msg: MSG, window_handle: HWND) -> bool {
+        if (self.messages_handler)(msg) {
+            return true
+        }
+
+        unsafe { RedrawWindow(window_handle, null_mut(), null_mut(), RDW_INVALIDATE); }
+        false
+    }
+}
+
+/// GDI backend of the paint code, draws to `hdc` of the `width`x`height` window.
+pub struct GdiRenderer<'a> {
+    hdc: HDC,
+    ps: &'a PAINTSTRUCT,
+    width: usize,
+    height: usize,
+}
+
+impl Renderer for GdiRenderer<'_> {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn clear(&mut self, color: Color) {
+        draw_fullscreen_rect(self.hdc, self.ps, color_ref(color));
+    }
+
+    fn fill_circle(&mut self, x: i32, y: i32, radius: i32, color: Color) {
+        let (brush, old_brush) = change_solid_brush(self.hdc, color_ref(color));
+        draw_circle(self.hdc, x, y, radius);
+        revert_brush(self.hdc, brush, old_brush);
+    }
+
+    fn polyline(&mut self, points: &[(i32, i32)], color: Color) {
+        let pts = points.iter().map(|(x, y)| POINT { x: *x, y: *y }).collect::<Vec<POINT>>();
+        let pen = create_solid_pen(self.hdc, color_ref(color));
+        unsafe { Polyline(self.hdc, pts.as_ptr(), pts.len() as i32) };
+        close_draw_lines(pen);
+    }
+
+    fn text(&mut self, x: i32, y: i32, text: &str, color: Color) {
+        let wide = text.encode_utf16().collect::<Vec<u16>>();
+        unsafe {
+            SetBkMode(self.hdc, TRANSPARENT as i32);
+            SetTextColor(self.hdc, color_ref(color));
+            TextOutW(self.hdc, x, y, wide.as_ptr(), wide.len() as i32);
+        }
+    }
+}
+
+/// Paint the world through a back buffer to avoid flickering.
+pub fn paint_frame(hdc: HDC, ps: &PAINTSTRUCT, app: &AppData) {
+    let frame = open_draw_frame(hdc, app.width as i32, app.height as i32);
+    let mut renderer = GdiRenderer {
+        hdc: frame.hdc,
+        ps,
+        width: app.width,
+        height: app.height,
+    };
+    render::paint_frame(&mut renderer, app);
+    close_draw_frame(hdc, app.width as i32, app.height as i32, frame);
+}
+
+#[inline(always)]
+fn color_ref(color: Color) -> u32 {
+    winapi::um::wingdi::RGB(color.r, color.g, color.b)
+}
@@ -0,0 +1,11 @@
+//! Platform-independent evolution engine.
+//! The desktop wallpaper front-end lives in the `live-wallpapers` binary (`windows-wallpaper` feature).
+
+pub mod live;
+
+pub use live::{LiveData, LiveSettings};
+pub use live::bacteries::Bacteries;
+pub use live::genome::Genome;
+pub use live::physics::PhysicsData;
+pub use live::bacteries_processing::process_bacteries;
+pub use live::simulation::Simulation;
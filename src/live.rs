@@ -1,32 +1,77 @@
+use std::collections::BTreeMap;
 use std::ops::Range;
 
 use micromath::vector::F32x2;
 
-use self::{physics::PhysicsData, utils::{rand_ranged_f32, rand_range_vec2}};
+use self::{genome::{scale_factor, Crossover, GenId, Mutation, MutationModel, ReproductionMode, MUTATION_RATE}, light::LightSpot, lineage::LineageRecord, physics::PhysicsData, settings_schema::SETTINGS, utils::{LiveRng, rand_ranged_f32, rand_range_vec2}};
 use rapier2d::prelude::*;
 
 pub mod app;
+pub mod simulation;
 pub mod physics;
-pub mod graphics;
+pub mod render;
 pub mod bacteries;
 pub mod genome;
+pub mod grid;
+pub mod light;
+pub mod lineage;
 pub mod bacteries_processing;
 pub mod save_load;
+pub mod presets;
+pub mod settings_schema;
+pub mod snapshot;
+pub mod species;
+pub mod export;
 pub mod utils;
 
 #[derive(Default)]
 pub struct LiveData {
+    /// Light of the day cycle, before the gradients, the spotlight and the shade.
     pub light_force: f32,
-    pub organic_matter: f32,
+    /// Light reaching each cell at this tick, see `update_light`.
+    pub light: grid::Grid,
+    /// Organic matter left by deaths, see `update_organic`.
+    pub organic: grid::Grid,
+    /// Total children since start, of divisions and matings.
+    pub births: u64,
+    /// Total deaths since start.
+    pub deaths: u64,
+    /// Id of the last born bacterium, ids start at 1.
+    pub last_id: u64,
+    pub lineage: lineage::Lineage,
+    pub species: species::SpeciesTracker,
     pub bacteries: bacteries::Bacteries,
+    /// `PhysicsData::contacts` seen by the last `process_collisions`, to find started contacts.
+    pub contacts: Vec<(usize, usize)>,
     pub physics_data: PhysicsData,
     pub settings: LiveSettings,
+    pub rng: LiveRng,
 }
 
 #[derive(Default, Debug)]
 pub struct LiveSettings {
+    /// Fixed simulation step in seconds, the same for physics and bacteries processing.
+    pub time_step : f32,
+    /// World speed for real-time front-ends: 1.0 is real time, 0.0 runs as fast as possible.
+    pub speed : f32,
+    /// Bacteria slots in the world, dead ones are reused. Fixed for the world lifetime.
+    pub capacity : u64,
     pub light_force : [f32; 4],
     pub day_length_sec : f32,
+    /// Side of a light cell in pixels.
+    pub light_cell_size : f32,
+    /// Light change from the middle to the right edge, as a share of the light.
+    pub light_gradient_x : f32,
+    /// Light change from the middle to the bottom edge, as a share of the light.
+    pub light_gradient_y : f32,
+    pub light_spot : LightSpot,
+    /// Spotlight position as a share of the world width and height.
+    pub light_spot_x : f32,
+    pub light_spot_y : f32,
+    /// Distance from the spotlight center in pixels at which the light falls to 1/e.
+    pub light_spot_radius : f32,
+    /// Share of the light a bacterium takes from the cells it covers.
+    pub light_shading : f32,
     pub move_force : f32,
     pub energy_for_move: f32,
     pub vel_range : Range<f32>,
@@ -49,6 +94,19 @@ pub struct LiveSettings {
 
     pub genome_mut_range : Range<f32>,
     pub radius_mut_range : Range<f32>,
+    /// Model of every gen without a `gene_mutation_models` entry.
+    pub mutation_model : MutationModel,
+    pub gene_mutation_models : BTreeMap<GenId, MutationModel>,
+    pub mutation_sigma : f32,
+    pub mutation_point_chance : f32,
+    pub mutation_jump_chance : f32,
+    pub mutation_jump_range : Range<f32>,
+
+    pub reproduction_mode : ReproductionMode,
+    pub crossover : Crossover,
+    pub mating_chance : f32,
+    /// Largest `Genome::distance` of the parents that can mate.
+    pub mating_distance : f32,
 
     pub flagella_num_range : Range<i32>,
     pub flagella_len_range : Range<i32>,
@@ -59,16 +117,42 @@ pub struct LiveSettings {
 
     pub on_dead_matter : f32,
     pub saprophyte_rate : f32,
+    /// Organic matter a saprophyte eats per second.
+    pub saprophyte_consumption : f32,
+    /// Side of an organic matter cell in pixels. Fixed for the world lifetime.
+    pub organic_cell_size : f32,
+    /// Share of organic matter a cell exchanges with each neighbour per second.
+    pub organic_diffusion : f32,
+    /// Share of organic matter that decays per second.
+    pub organic_decay : f32,
+
+    /// Events per simulated second of a `*_rate` gene equal to 1.0.
+    pub max_gen_rate : f32,
+
+    /// Seed of the world random generator, 0 means a random seed.
+    pub seed : u64,
+
+    /// Simulated seconds between species updates, 0 disables them.
+    pub species_interval_sec : f32,
+    /// Largest `Genome::distance` from the representative of a species.
+    pub species_distance : f32,
+
+    /// Simulated seconds between autosave snapshots, 0 disables autosave.
+    pub autosave_interval_sec : f32,
+    /// Number of the newest autosave snapshots kept, older ones are deleted.
+    pub autosave_keep : u64,
 }
 
 impl LiveData {
-    pub fn spawn_bac(&mut self, pos: F32x2, radius: i32) {
+    pub fn spawn_bac(&mut self, pos: F32x2, radius: i32, tick: u128) {
         for i in self.bacteries.into_iter() {
             if self.bacteries.is_dead(i, self.settings.dead_time) {
+                self.register_birth(i, tick, None, None);
                 self.bacteries.pos[i] = pos;
                 self.bacteries.radius[i] = radius;
-                self.bacteries.left_time[i] = rand_ranged_f32(self.settings.start_alive_range.clone());
-                self.bacteries.genome.default_one(i);
+                self.bacteries.left_time[i] = rand_ranged_f32(&mut self.rng, self.settings.start_alive_range.clone());
+                self.bacteries.energy[i] = self.settings.start_energy;
+                self.bacteries.genome.default_one(i, &mut self.rng);
 
                 let rb = self.physics_data.get_rb_mut(self.bacteries.rigidbody[i]);
                 rb.set_position(Isometry::new(vector![pos.x, pos.y], 0.0), true);
@@ -82,134 +166,185 @@ impl LiveData {
         }
     }
 
-    pub fn mut_clone(&mut self, src: usize) {
+    pub fn mut_clone(&mut self, src: usize, tick: u128) {
         for i in self.bacteries.into_iter() {
             if self.bacteries.is_dead(i, self.settings.dead_time) {
-                let pos = self.bacteries.pos[src];
-                let mut radius = (self.bacteries.radius[src] as f32 * rand_ranged_f32(self.settings.radius_mut_range.clone())) as i32;
-                radius = radius.clamp(self.settings.radius_range.start, self.settings.radius_range.end);
-                self.bacteries.pos[i] = pos + rand_range_vec2(-0.1..0.1, -0.1..0.1);
-                self.bacteries.radius[i] = radius;
-                self.bacteries.left_time[i] = rand_ranged_f32(self.settings.start_alive_range.clone());
-                self.bacteries.parent[i] = src;
-                self.bacteries.is_parented[i] = true;
-                self.bacteries.genome.mut_clone(src, i, self.settings.genome_mut_range.clone());
+                self.register_birth(i, tick, Some(src), None);
+                let factor = rand_ranged_f32(&mut self.rng, self.settings.radius_mut_range.clone());
+                let rate = self.bacteries.genome[MUTATION_RATE][src];
+                let radius = self.bacteries.radius[src] as f32 * scale_factor(factor, rate);
+                self.place_child(i, src, radius, self.settings.start_energy);
+                self.bacteries.genome.mut_clone(src, i, &Mutation::new(&self.settings), &mut self.rng);
+                return;
+            }
+        }
+    }
 
-                let rb = self.physics_data.get_rb_mut(self.bacteries.rigidbody[i]);
-                rb.set_position(Isometry::new(vector![pos.x, pos.y], 0.0), true);
-                rb.set_enabled(false);
+    /// Child of `a` and `b` next to `a`: crossover of the parents, then mutation. The radius is the parents mean, mutated.
+    /// The child starts with the `energy` the parents paid.
+    pub fn mate(&mut self, a: usize, b: usize, energy: f32, tick: u128) {
+        for i in self.bacteries.into_iter() {
+            if self.bacteries.is_dead(i, self.settings.dead_time) {
+                self.register_birth(i, tick, Some(a), Some(b));
+                self.bacteries.genome.crossover(a, b, i, self.settings.crossover, &Mutation::new(&self.settings), &mut self.rng);
 
-                let coll = self.physics_data.get_coll_mut(self.bacteries.collider[i]);
-                coll.shape_mut().as_ball_mut().unwrap().radius = radius as f32;
-                coll.set_enabled(true);
-                self.bacteries.genome.normilize_one(i);
+                let factor = rand_ranged_f32(&mut self.rng, self.settings.radius_mut_range.clone());
+                let rate = self.bacteries.genome[MUTATION_RATE][i];
+                let radius = (self.bacteries.radius[a] + self.bacteries.radius[b]) as f32 * 0.5 * scale_factor(factor, rate);
+                self.place_child(i, a, radius, energy);
                 return;
             }
         }
     }
 
+    /// Give the slot `i` a new id and record where the bacterium comes from, `parent` and `mate` are slots.
+    pub fn register_birth(&mut self, i: usize, tick: u128, parent: Option<usize>, mate: Option<usize>) {
+        let bac = &mut self.bacteries;
+        let generation = parent.iter().chain(mate.iter()).map(|&p| bac.generation[p] + 1).max().unwrap_or(0);
+        bac.parent_id[i] = parent.map_or(0, |p| bac.id[p]);
+        bac.mate_id[i] = mate.map_or(0, |p| bac.id[p]);
+        bac.generation[i] = generation;
+        bac.species[i] = parent.map_or(0, |p| bac.species[p]);
+        bac.birth_tick[i] = tick;
+        self.last_id += 1;
+        bac.id[i] = self.last_id;
+        self.lineage.record(self.last_id, LineageRecord { parent_id: bac.parent_id[i], birth_tick: tick, generation });
+    }
+
+    /// Put the new bacterium `i` on its `parent`, it leaves the parent in `process_division_movement`.
+    fn place_child(&mut self, i: usize, parent: usize, radius: f32, energy: f32) {
+        let pos = self.bacteries.pos[parent];
+        let radius = (radius as i32).clamp(self.settings.radius_range.start, self.settings.radius_range.end);
+        self.bacteries.pos[i] = pos + rand_range_vec2(&mut self.rng, -0.1..0.1, -0.1..0.1);
+        self.bacteries.radius[i] = radius;
+        self.bacteries.left_time[i] = rand_ranged_f32(&mut self.rng, self.settings.start_alive_range.clone());
+        self.bacteries.energy[i] = energy;
+        self.bacteries.parent[i] = parent;
+        self.bacteries.is_parented[i] = true;
+
+        let rb = self.physics_data.get_rb_mut(self.bacteries.rigidbody[i]);
+        rb.set_position(Isometry::new(vector![pos.x, pos.y], 0.0), true);
+        rb.set_enabled(false);
+
+        let coll = self.physics_data.get_coll_mut(self.bacteries.collider[i]);
+        coll.shape_mut().as_ball_mut().unwrap().radius = radius as f32;
+        coll.set_enabled(true);
+        self.births += 1;
+    }
+
     pub fn kill_bac(&mut self, idx: usize) {
         self.physics_data.get_rb_mut(self.bacteries.rigidbody[idx]).set_enabled(false);
         self.physics_data.get_coll_mut(self.bacteries.collider[idx]).set_enabled(false);
-        self.organic_matter = (self.settings.on_dead_matter + self.organic_matter).clamp(0.0, 10000.0); // ToDo: to const ?
+        let cells = self.organic.cells_in_circle(self.bacteries.pos[idx], self.bacteries.radius[idx] as f32);
+        for &cell in cells.iter() {
+            self.organic.cells[cell] += self.settings.on_dead_matter / cells.len() as f32;
+        }
+        self.deaths += 1;
+    }
+
+    /// Ids of the living bacteries.
+    pub fn living_ids(&self) -> Vec<u64> {
+        let bac = &self.bacteries;
+        bac.into_iter().filter(|i| bac.is_alive(*i, self.settings.dead_time)).map(|i| bac.id[i]).collect()
+    }
+
+    /// Drop the lineage records of extinct lines once enough of them piled up.
+    pub fn prune_lineage(&mut self) {
+        if self.lineage.needs_prune() {
+            let living = self.living_ids();
+            self.lineage.prune(&living, self.bacteries.num);
+        }
+    }
+
+    /// Cluster the bacteries into species every `species_interval_sec`.
+    pub fn update_species(&mut self, tick: u128) {
+        if self.settings.species_interval_sec <= 0.0 {
+            return;
+        }
+
+        if self.species.next_tick.is_some_and(|next| tick < next) {
+            return;
+        }
+        let interval = (self.settings.species_interval_sec / self.settings.time_step).round().max(1.0) as u128;
+        self.species.next_tick = Some(tick + interval);
+        self.species.update(&mut self.bacteries, self.settings.dead_time, self.settings.species_distance, tick);
+    }
+
+    /// Spread and decay the organic matter over `delta_time` seconds.
+    pub fn update_organic(&mut self, delta_time: f32) {
+        self.organic.diffuse(self.settings.organic_diffusion * delta_time);
+        let kept = (-self.settings.organic_decay * delta_time).exp();
+        for value in self.organic.cells.iter_mut() {
+            *value *= kept;
+        }
+    }
+
+    /// Light every cell of a `width` x `height` world: `light_force` scaled by the gradients and the spotlight,
+    /// then shaded by every living bacterium over the cell. The grid is rebuilt when `light_cell_size` changes.
+    pub fn update_light(&mut self, width: usize, height: usize, day_progress: f32) {
+        if self.light.cells.is_empty() || self.light.cell_size != self.settings.light_cell_size.max(1.0) {
+            self.light = grid::Grid::new(width, height, self.settings.light_cell_size);
+        }
+
+        for idx in 0..self.light.cells.len() {
+            let factor = light::light_factor(&self.settings, self.light.center(idx), width as f32, height as f32, day_progress);
+            self.light.cells[idx] = self.light_force * factor;
+        }
+
+        let kept = 1.0 - self.settings.light_shading;
+        let bac = &self.bacteries;
+        for i in bac.into_iter().filter(|i| bac.is_alive(*i, self.settings.dead_time)) {
+            for cell in self.light.cells_in_circle(bac.pos[i], bac.radius[i] as f32) {
+                self.light.cells[cell] *= kept;
+            }
+        }
+    }
+
+    /// Light the living bacterium `i` gets at its position, its own shade left out.
+    pub fn light_at(&self, i: usize) -> f32 {
+        match self.light.cell_at(self.bacteries.pos[i]) {
+            Some(cell) => self.light.cells[cell] / (1.0 - self.settings.light_shading),
+            None => self.light_force,
+        }
+    }
+
+    /// Copy rigidbody positions to `bacteries.pos`.
+    pub fn sync_positions(&mut self) {
+        for i in self.bacteries.into_iter() {
+            let body = self.physics_data.get_rb(self.bacteries.rigidbody[i]);
+            let pos = body.position();
+            self.bacteries.pos[i].x = pos.translation.x;
+            self.bacteries.pos[i].y = pos.translation.y;
+        }
     }
 }
 
 impl LiveSettings {
+    /// Defaults of `settings_schema::SETTINGS`.
     pub fn new() -> LiveSettings {
-        LiveSettings {
-            light_force: [0.2, 0.65, 1.0, 0.2],
-            day_length_sec: 8.0 * 60.0,
-            move_force: 100.0,
-            energy_for_move: 0.5,
-            vel_range: -1.0..1.0,
-            radius_range: 8..20,
-            max_alive: 100.0,
-            start_alive_range: 1.0..100.0,
-            dead_time: 0.0,
-            start_energy: 1.0,
-            division_energy: 10.0,
-            alive_to_energy_coef: 0.1,
-            photosynth_rate: 0.02,
-            carnivore_rate: 10.0,
-            carnivore_damage: 15.0,
-            defence: 15.0,
-            carnivore_cost: 20.0,
-            genome_mut_range: 0.9..1.1,
-            radius_mut_range: 0.9..1.1,
-            flagella_num_range: 6..14,
-            flagella_len_range: 2..8,
-            max_energy_distribution: 10.0,
-            max_repulsive_force: 300.0,
-            on_dead_matter: 0.5,
-            saprophyte_rate: 1.0,
+        let mut result = LiveSettings::default();
+        for setting in SETTINGS.iter() {
+            setting.set(&mut result, setting.default.clone()).unwrap();
         }
+        result
     }
 }
 
 impl std::fmt::Display for LiveSettings {
-fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "LiveSettings
-{{
-    light_force: {:?},
-    day_length_sec: {}
-    move_force: {:?},
-    energy_for_move: {:?},
-    vel_range: {:?},
-    radius_range: {:?},
-    max_alive: {:?},
-    start_alive_range: {:?},
-    dead_time: {:?},
-    start_energy: {:?},
-    division_energy: {:?},
-    alive_to_energy_coef: {:?},
-    photosynth_rate: {:?},
-    carnivore_rate: {:?},
-    carnivore_damage: {:?},
-    defence: {:?},
-    carnivore_cost: {:?},
-    genome_mut_range: {:?},
-    radius_mut_range: {:?},
-    flagella_num_range: {:?},
-    flagella_len_range: {:?},
-    max_energy_distribution: {:?},
-    max_repulsive_force: {:?},
-    on_dead_matter: {:?},
-    saprophyte_rate: {:?},
-}}",
-        self.light_force,
-        self.day_length_sec,
-        self.move_force,
-        self.energy_for_move,
-        self.vel_range,
-        self.radius_range,
-        self.max_alive,
-        self.start_alive_range,
-        self.dead_time,
-        self.start_energy,
-        self.division_energy,
-        self.alive_to_energy_coef,
-        self.photosynth_rate,
-        self.carnivore_rate,
-        self.carnivore_damage,
-        self.defence,
-        self.carnivore_cost,
-        self.genome_mut_range,
-        self.radius_mut_range,
-        self.flagella_num_range,
-        self.flagella_len_range,
-        self.max_energy_distribution,
-        self.max_repulsive_force,
-        self.on_dead_matter,
-        self.saprophyte_rate)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "LiveSettings\n{{")?;
+        for setting in SETTINGS.iter() {
+            writeln!(f, "    {}: {},", setting.name, setting.get(self))?;
+        }
+        write!(f, "}}")
     }
 }
 
 pub fn normalize_f32x2(v: &mut F32x2) {
     let len = (v.x * v.x + v.y * v.y).sqrt();
     if len > 0.0 {
-        v.x = v.x / len;
-        v.y = v.y / len;
+        v.x /= len;
+        v.y /= len;
     }
 }
 
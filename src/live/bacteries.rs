@@ -3,7 +3,7 @@ use std::{f32::consts::PI, ops::Range};
 use micromath::vector::F32x2;
 use rapier2d::prelude::*;
 
-use super::{genome::Genome, utils::{rand_ranged_f32, rand_range_vec2, rand_ranged_i32}};
+use super::{genome::Genome, utils::{LiveRng, rand_ranged_f32, rand_range_vec2, rand_ranged_i32}};
 
 pub struct Collision {
     pub a: usize,
@@ -17,8 +17,20 @@ pub struct Bacteries {
     pub radius: Vec<i32>,
     pub left_time: Vec<f32>,
     pub energy: Vec<f32>,
+    /// Slot of the parent while the child leaves it, see `is_parented`.
     pub parent: Vec<usize>,
     pub is_parented: Vec<bool>,
+    /// Unique organism id from `LiveData::last_id`, slots are reused but ids are not. 0 if nobody was born in the slot.
+    pub id: Vec<u64>,
+    pub birth_tick: Vec<u128>,
+    /// `id` of the divided parent or of the first mate, 0 for spawned bacteries.
+    pub parent_id: Vec<u64>,
+    /// `id` of the second mate, 0 if not mated.
+    pub mate_id: Vec<u64>,
+    /// Spawned bacteries are 0, a child is one more than its parents.
+    pub generation: Vec<u32>,
+    /// `Species::id`, children start in the species of their parent. 0 until the first species update.
+    pub species: Vec<u64>,
     pub rigidbody: Vec<RigidBodyHandle>,
     pub collider: Vec<ColliderHandle>,
     pub genome: Genome,
@@ -28,8 +40,8 @@ pub struct Bacteries {
 
 impl Bacteries {
     #[inline(always)]
-    pub fn new(num: usize) -> Bacteries {
-        let result = Bacteries {
+    pub fn new(num: usize, rng: &mut LiveRng) -> Bacteries {
+        Bacteries {
             num,
             pos: vec![F32x2::default(); num],
             radius: vec![i32::default(); num],
@@ -37,19 +49,23 @@ impl Bacteries {
             energy: vec![0.0; num],
             parent: vec![0; num],
             is_parented: vec![false; num],
+            id: vec![0; num],
+            birth_tick: vec![0; num],
+            parent_id: vec![0; num],
+            mate_id: vec![0; num],
+            generation: vec![0; num],
+            species: vec![0; num],
 
             rigidbody: Vec::with_capacity(num),
             collider: Vec::with_capacity(num),
 
-            genome: Genome::new(num),
-        };
-
-        result
+            genome: Genome::new(num, rng),
+        }
     }
 
     #[inline(always)]
-    pub const fn empty() -> Bacteries {
-        let result = Bacteries {
+    pub fn empty() -> Bacteries {
+        Bacteries {
             num: 0,
             pos: vec![],
             radius: vec![],
@@ -57,33 +73,37 @@ impl Bacteries {
             energy: vec![],
             parent: vec![],
             is_parented: vec![],
+            id: vec![],
+            birth_tick: vec![],
+            parent_id: vec![],
+            mate_id: vec![],
+            generation: vec![],
+            species: vec![],
 
             rigidbody: vec![],
             collider: vec![],
 
             genome: Genome::empty(),
-        };
-
-        result
+        }
     }
 
     #[inline(always)]
-    pub fn rand_in_rect(num: usize, capacity: usize, x: Range::<f32>, y: Range::<f32>, start_alive_range: Range<f32>) -> Bacteries {
-        let mut result = Bacteries::new(capacity);
+    pub fn rand_in_rect(num: usize, capacity: usize, x: Range::<f32>, y: Range::<f32>, start_alive_range: Range<f32>, rng: &mut LiveRng) -> Bacteries {
+        let mut result = Bacteries::new(capacity, rng);
 
         for i in 0..num {
-            result.left_time[i] = rand_ranged_f32(start_alive_range.clone());
-            result.pos.push(rand_range_vec2(x.clone(), y.clone()));
+            result.left_time[i] = rand_ranged_f32(rng, start_alive_range.clone());
+            result.pos.push(rand_range_vec2(rng, x.clone(), y.clone()));
         }
 
         result
     }
 
     #[inline(always)]
-    pub fn set_random_radius(&mut self, min: i32, max: i32) {
+    pub fn set_random_radius(&mut self, min: i32, max: i32, rng: &mut LiveRng) {
         self.radius = Vec::with_capacity(self.num);
         for _ in self.into_iter() {
-            self.radius.push(rand_ranged_i32(min..max))
+            self.radius.push(rand_ranged_i32(rng, min..max))
         }
     }
 
@@ -110,7 +130,6 @@ impl Bacteries {
                 let collider = ColliderBuilder::ball(radius)
                     .mass(4.0/3.0 * PI * radius * radius)
                     .user_data(i as u128)
-                    .active_events(ActiveEvents::COLLISION_EVENTS)
                     .build();
                 let rb = self.rigidbody[i];
                 self.collider.push(colliders_set.insert_with_parent(collider, rb, rigidbody_set));
@@ -119,7 +138,7 @@ impl Bacteries {
     }
 
     #[inline(always)]
-    pub fn draw<T: Fn(F32x2, i32) -> ()>(&self, draw_func: T) {
+    pub fn draw<T: Fn(F32x2, i32)>(&self, draw_func: T) {
         for i in self.into_iter() {
             draw_func(self.pos[i], self.radius[i]);
         }
@@ -7,7 +7,8 @@ use rapier2d::na::Vector2;
 use crate::live::LiveData;
 
 use super::app::AppData;
-use super::utils::{rand_range_vec2, rand_ranged_f32};
+use super::genome::*;
+use super::utils::{LiveRng, rand_chance, rand_range_vec2, rand_ranged_f32};
 use super::{normalize_f32x2, len_f32x2};
 
 pub fn process_bacteries(app: &mut AppData) {
@@ -31,13 +32,12 @@ fn process_alive(app: &mut AppData) {
             continue;
         }
 
-        if calc_rate(live.bacteries.genome.live_regen_rate[i]){
-            if left_time < live.settings.max_alive - live.settings.alive_to_energy_coef {
-                let energy = &mut live.bacteries.energy[i];
-                if *energy > 2.0 {
-                    *energy -= 1.0;
-                    left_time += live.settings.alive_to_energy_coef;
-                }
+        if calc_rate(live.bacteries.genome[LIVE_REGEN_RATE][i], live.settings.max_gen_rate, app.delta_time, &mut live.rng) &&
+            left_time < live.settings.max_alive - live.settings.alive_to_energy_coef {
+            let energy = &mut live.bacteries.energy[i];
+            if *energy > 2.0 {
+                *energy -= 1.0;
+                left_time += live.settings.alive_to_energy_coef;
             }
         }
 
@@ -58,13 +58,13 @@ fn process_movement(app: &mut AppData) {
             continue;
         }
 
-        if calc_rate(bac.genome.movement_rate[i]) {
-            let force = bac.genome.movement_force[i] * app.live_data.settings.move_force;
-            let vel = rand_range_vec2(vel_range.clone(), vel_range.clone()) * force;
+        if calc_rate(bac.genome[MOVEMENT_RATE][i], app.live_data.settings.max_gen_rate, app.delta_time, &mut app.live_data.rng) {
+            let force = bac.genome[MOVEMENT_FORCE][i] * app.live_data.settings.move_force;
+            let vel = rand_range_vec2(&mut app.live_data.rng, vel_range.clone(), vel_range.clone()) * force;
             let vel_vec = Vector2::new(vel.x, vel.y);                
             app.live_data.physics_data.get_rb_mut(bac.rigidbody[i]).add_force(vel_vec, true);
 
-            bac.energy[i] -= bac.genome.movement_force[i] * app.live_data.settings.energy_for_move;
+            bac.energy[i] -= bac.genome[MOVEMENT_FORCE][i] * app.live_data.settings.energy_for_move;
         }
     }
 }
@@ -72,7 +72,7 @@ fn process_movement(app: &mut AppData) {
 fn process_photosynth(app: &mut AppData) {
     let live = &mut app.live_data;
     for i in live.bacteries.into_iter() {
-        let photosynth = live.bacteries.genome.photosynth[i];
+        let photosynth = live.bacteries.genome[PHOTOSYNTH][i];
         if live.bacteries.is_dead(i, live.settings.dead_time) || photosynth == 0.0 {
             continue;
         }
@@ -84,42 +84,52 @@ fn process_photosynth(app: &mut AppData) {
             app.delta_time *
             PI *
             (radius * radius) as f32 *
-            live.light_force;
+            live.light_at(i);
     }
 }
 
+/// Saprophytes eat the organic matter of the cells they overlap, the gain shrinks with what is left there.
 fn process_saprophyte(app: &mut AppData) {
     let live = &mut app.live_data;
+    let want = live.settings.saprophyte_consumption * app.delta_time;
     for i in live.bacteries.into_iter() {
-        if live.organic_matter <= 1.0 {
-            return;
+        let saprophyte = live.bacteries.genome[SAPROPHYTE][i];
+        if live.bacteries.is_dead(i, live.settings.dead_time) || saprophyte == 0.0 || want <= 0.0 {
+            continue;
         }
-        let saprophyte = live.bacteries.genome.saprophyte[i];
-        if live.bacteries.is_dead(i, live.settings.dead_time) || saprophyte == 0.0 {
+
+        let cells = live.organic.cells_in_circle(live.bacteries.pos[i], live.bacteries.radius[i] as f32);
+        let available = cells.iter().map(|&cell| live.organic.cells[cell]).sum::<f32>();
+        if available <= 0.0 {
             continue;
         }
 
-        live.organic_matter -= 1.0;
+        let eaten = want.min(available);
+        let left = 1.0 - eaten / available;
+        for &cell in cells.iter() {
+            live.organic.cells[cell] *= left;
+        }
         live.bacteries.energy[i] +=
             saprophyte *
             live.settings.saprophyte_rate *
-            app.delta_time;
+            app.delta_time *
+            eaten / want;
     }
 }
 
+/// Carnivory, energy distribution and repulsion for every step of a contact, in the order of the pairs.
+/// Mating once, when the contact starts.
 fn process_collisions(app: &mut AppData) {
-    if app.live_data.physics_data.events.collisions.is_none() {
-        return;
-    }
+    let contacts = app.live_data.physics_data.contacts();
+    let previous = std::mem::replace(&mut app.live_data.contacts, contacts.clone());
 
-    let mut collisions = app.live_data.physics_data.events.collisions.take().unwrap();
-    for col in collisions.iter_mut() {
-        let physics = &mut app.live_data.physics_data;
-        let a = physics.get_coll(col.collider1()).user_data as usize;
-        let b = physics.get_coll(col.collider2()).user_data as usize;
+    for (a, b) in contacts {
         process_carnivore(app, a, b);
         process_energy_distribution(app, a, b);
         process_repulsive(app, a, b);
+        if previous.binary_search(&(a, b)).is_err() {
+            process_mating(app, a, b);
+        }
     }
 }
 
@@ -131,10 +141,10 @@ fn process_carnivore(app: &mut AppData, a: usize, b: usize) {
     let cost = settings.carnivore_cost;
 
     let bac = &mut app.live_data.bacteries;
-    let cav_a = bac.genome.carnivore[a];
-    let cav_b = bac.genome.carnivore[b];
-    let dam_for_a = damage - bac.genome.defence[a] * defence;
-    let dam_for_b = damage - bac.genome.defence[b] * defence;
+    let cav_a = bac.genome[CARNIVORE][a];
+    let cav_b = bac.genome[CARNIVORE][b];
+    let dam_for_a = damage - bac.genome[DEFENCE][a] * defence;
+    let dam_for_b = damage - bac.genome[DEFENCE][b] * defence;
 
     bac.left_time[a] -= (dam_for_a * (cav_b - cav_a).clamp(0.0, f32::MAX)) * app.delta_time;
     bac.left_time[b] -= (dam_for_b * (cav_a - cav_b).clamp(0.0, f32::MAX)) * app.delta_time;
@@ -146,8 +156,8 @@ fn process_carnivore(app: &mut AppData, a: usize, b: usize) {
 fn process_energy_distribution(app: &mut AppData, a: usize, b: usize) {
     let en_distr = app.live_data.settings.max_energy_distribution;
     let bac = &mut app.live_data.bacteries;
-    let dis_a = bac.genome.energy_distribution[a];
-    let dis_b = bac.genome.energy_distribution[b];
+    let dis_a = bac.genome[ENERGY_DISTRIBUTION][a];
+    let dis_b = bac.genome[ENERGY_DISTRIBUTION][b];
 
     let a_to_b = dis_a * en_distr * app.delta_time;
     let b_to_a = dis_b * en_distr * app.delta_time;
@@ -160,6 +170,7 @@ fn process_energy_distribution(app: &mut AppData, a: usize, b: usize) {
 }
 
 fn process_repulsive(app: &mut AppData, a: usize, b: usize) {
+    let dt = app.delta_time;
     let data = &mut app.live_data;
 
     let pos_a = data.bacteries.pos[a];
@@ -167,32 +178,62 @@ fn process_repulsive(app: &mut AppData, a: usize, b: usize) {
     let mut a_to_b = pos_b - pos_a;
     normalize_f32x2(&mut a_to_b);
 
-    try_repulsive(data, a, b, a_to_b);
-    try_repulsive(data, b, a, a_to_b * -1.0);
+    try_repulsive(data, a, b, a_to_b, dt);
+    try_repulsive(data, b, a, a_to_b * -1.0, dt);
 
-    fn try_repulsive(data: &mut LiveData, cur: usize, other: usize, dir: F32x2) {
-        if calc_rate(data.bacteries.genome.repulsive_rate[cur]) {
+    fn try_repulsive(data: &mut LiveData, cur: usize, other: usize, dir: F32x2, dt: f32) {
+        if calc_rate(data.bacteries.genome[REPULSIVE_RATE][cur], data.settings.max_gen_rate, dt, &mut data.rng) {
             let other_rb = data.bacteries.rigidbody[other];
-            let force = data.bacteries.genome.repulsive_force[cur];
+            let force = data.bacteries.genome[REPULSIVE_FORCE][cur];
             let force = Vector2::new(dir.x, dir.y) * data.settings.max_repulsive_force * force;
             data.physics_data.get_rb_mut(other_rb).add_force(force, true);
         }
     }
 }
 
+/// A new contact of two compatible bacteries with half of `division_energy` each may give a child of both.
+fn process_mating(app: &mut AppData, a: usize, b: usize) {
+    let live = &mut app.live_data;
+    let settings = &live.settings;
+    if !settings.reproduction_mode.mating() {
+        return;
+    }
+
+    let bac = &live.bacteries;
+    // A child leaving its parent touches it.
+    if bac.is_parented[a] || bac.is_parented[b] || bac.is_dead(a, settings.dead_time) || bac.is_dead(b, settings.dead_time) {
+        return;
+    }
+
+    let share = settings.division_energy * 0.5;
+    if bac.energy[a] < share || bac.energy[b] < share || bac.genome.distance(a, b) > settings.mating_distance {
+        return;
+    }
+
+    if rand_chance(&mut live.rng, settings.mating_chance) {
+        live.bacteries.energy[a] -= share;
+        live.bacteries.energy[b] -= share;
+        live.mate(a, b, share * 2.0, app.frame_num);
+    }
+}
+
 fn process_division(app: &mut AppData) {
     let deat_time = app.live_data.settings.dead_time;
     let live = &mut app.live_data;
+    if !live.settings.reproduction_mode.division() {
+        return;
+    }
+
     for i in live.bacteries.into_iter() {
         if live.bacteries.is_dead(i, deat_time) {
             continue;
         }
 
-        if calc_rate(live.bacteries.genome.division_rate[i]) {
+        if calc_rate(live.bacteries.genome[DIVISION_RATE][i], live.settings.max_gen_rate, app.delta_time, &mut live.rng) {
             let energy = &mut live.bacteries.energy[i];
             if *energy >= live.settings.division_energy {
                 *energy -= live.settings.division_energy;
-                live.mut_clone(i);
+                live.mut_clone(i, app.frame_num);
             }
         }
     }
@@ -222,6 +263,92 @@ fn process_division_movement(app: &mut AppData) {
     }
 }
 
-fn calc_rate(rate: f32) -> bool {
-    rate > rand_ranged_f32(0.0..1.0)
-}
\ No newline at end of file
+/// `rate` gene is `rate * max_gen_rate` events per simulated second,
+/// converted to the chance of at least one event during the `dt` step.
+fn calc_rate(rate: f32, max_gen_rate: f32, dt: f32, rng: &mut LiveRng) -> bool {
+    let probability = 1.0 - (-rate * max_gen_rate * dt).exp();
+    probability > rand_ranged_f32(rng, 0.0..1.0)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::LiveSettings;
+
+    /// Steps with an event during `seconds` simulated seconds at `hz` steps per second.
+    fn events(hz: f32, seconds: f32, rng: &mut LiveRng) -> u32 {
+        let steps = (seconds * hz) as u32;
+        (0..steps).filter(|_| calc_rate(0.25, 2.0, 1.0 / hz, rng)).count() as u32
+    }
+
+    #[test]
+    fn calc_rate_does_not_depend_on_the_tick_rate() {
+        let mut rng = LiveRng::new(3);
+        // 0.5 events per second.
+        let slow = events(30.0, 2000.0, &mut rng);
+        let fast = events(120.0, 2000.0, &mut rng);
+        assert!((900..1100).contains(&slow), "{}", slow);
+        assert!((900..1100).contains(&fast), "{}", fast);
+    }
+
+    /// Two living bacteries with equal genomes and enough energy to mate, with certain mating and a free slot.
+    fn mating_pair() -> (AppData, usize, usize) {
+        let mut settings = LiveSettings::new();
+        settings.capacity = 20;
+        settings.reproduction_mode = ReproductionMode::Sexual;
+        settings.mating_chance = 1.0;
+        let mut app = AppData::new(400, 300, settings.time_step);
+        app.live_data.settings = settings;
+        app.build_physics();
+        let radius = app.live_data.settings.radius_range.clone();
+        app.spawn_bacteries(radius);
+
+        let live = &mut app.live_data;
+        let mut alive = live.bacteries.into_iter().filter(|&i| live.bacteries.is_alive(i, live.settings.dead_time));
+        let (a, b) = (alive.next().unwrap(), alive.next().unwrap());
+        let free = alive.next().unwrap();
+        live.bacteries.left_time[free] = live.settings.dead_time;
+        for gen in live.bacteries.genome.iter_mut() {
+            gen[b] = gen[a];
+        }
+        live.bacteries.energy[a] = live.settings.division_energy;
+        live.bacteries.energy[b] = live.settings.division_energy;
+        (app, a, b)
+    }
+
+    #[test]
+    fn compatible_bacteries_mate() {
+        let (mut app, a, b) = mating_pair();
+        let births = app.live_data.births;
+        process_mating(&mut app, a, b);
+        let live = &app.live_data;
+        assert_eq!(live.births, births + 1);
+        let share = live.settings.division_energy * 0.5;
+        assert_eq!((live.bacteries.energy[a], live.bacteries.energy[b]), (share, share));
+        let child = live.bacteries.into_iter().find(|&i| live.bacteries.id[i] == live.last_id).unwrap();
+        assert_eq!(live.bacteries.energy[child], share * 2.0);
+    }
+
+    #[test]
+    fn mating_needs_mode_energy_and_similar_genomes() {
+        let gates: [fn(&mut AppData, usize, usize); 5] = [
+            |app, _, _| app.live_data.settings.reproduction_mode = ReproductionMode::Asexual,
+            |app, _, _| app.live_data.settings.mating_chance = 0.0,
+            |app, a, _| app.live_data.bacteries.energy[a] *= 0.4,
+            |app, _, b| app.live_data.bacteries.is_parented[b] = true,
+            |app, a, b| {
+                app.live_data.settings.mating_distance = 0.1;
+                app.live_data.bacteries.genome[PHOTOSYNTH][a] += 1.0;
+                app.live_data.bacteries.genome.normilize_one(a);
+                assert!(app.live_data.bacteries.genome.distance(a, b) > 0.1);
+            },
+        ];
+        for gate in gates {
+            let (mut app, a, b) = mating_pair();
+            gate(&mut app, a, b);
+            let births = app.live_data.births;
+            process_mating(&mut app, a, b);
+            assert_eq!(app.live_data.births, births);
+        }
+    }
+}
+
@@ -0,0 +1,193 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::app::AppData;
+use super::render::{paint_frame, framebuffer::Framebuffer};
+
+/// Destination of rendered frames.
+pub trait FrameSink {
+    /// `frame_num` is the simulation tick the frame shows.
+    fn write_frame(&mut self, frame: &Framebuffer, frame_num: u128) -> io::Result<()>;
+
+    /// Flush buffered output, called once after the last frame.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Paints every `every`-th tick with the software renderer and passes the frame to the sink.
+pub struct FrameRecorder {
+    every: u128,
+    framebuffer: Framebuffer,
+    sink: Box<dyn FrameSink>,
+}
+
+impl FrameRecorder {
+    /// `width`x`height` is the frame resolution, the world is scaled to it.
+    pub fn new(every: u128, width: usize, height: usize, sink: Box<dyn FrameSink>) -> FrameRecorder {
+        FrameRecorder {
+            every: every.max(1),
+            framebuffer: Framebuffer::new(width, height),
+            sink,
+        }
+    }
+
+    /// Does nothing if the current tick is not a multiple of `every`.
+    pub fn capture(&mut self, app: &AppData) -> io::Result<()> {
+        if !app.frame_num.is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        paint_frame(&mut self.framebuffer, app);
+        self.sink.write_frame(&self.framebuffer, app.frame_num)
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.sink.finish()
+    }
+}
+
+/// `frame_000123.png` files in one directory, the number is the tick.
+pub struct PngSequence {
+    dir: PathBuf,
+}
+
+impl PngSequence {
+    /// Creates `dir` if it is missing.
+    pub fn new(dir: &Path) -> io::Result<PngSequence> {
+        fs::create_dir_all(dir)?;
+        Ok(PngSequence {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl FrameSink for PngSequence {
+    fn write_frame(&mut self, frame: &Framebuffer, frame_num: u128) -> io::Result<()> {
+        write_png(&self.dir.join(format!("frame_{:06}.png", frame_num)), frame)
+    }
+}
+
+pub fn write_png(path: &Path, frame: &Framebuffer) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, frame.width() as u32, frame.height() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(frame.pixels())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// YUV4MPEG2 (4:2:0, full range) video stream, e.g. to pipe into an encoder.
+/// The header is written with the size of the first frame.
+pub struct Y4mStream<W: Write> {
+    out: W,
+    fps: u32,
+    size: Option<(usize, usize)>,
+    planes: Vec<u8>,
+}
+
+impl<W: Write> Y4mStream<W> {
+    pub fn new(out: W, fps: u32) -> Y4mStream<W> {
+        Y4mStream {
+            out,
+            fps: fps.max(1),
+            size: None,
+            planes: Vec::new(),
+        }
+    }
+
+    /// Convert the RGBA frame to Y, U, V planes, chroma is averaged over 2x2 blocks.
+    fn fill_planes(&mut self, frame: &Framebuffer) {
+        let (width, height) = (frame.width(), frame.height());
+        let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+        let chroma_len = chroma_width * chroma_height;
+        self.planes.clear();
+        self.planes.resize(width * height + chroma_len * 2, 0);
+        let (y_plane, uv_planes) = self.planes.split_at_mut(width * height);
+        let (u_plane, v_plane) = uv_planes.split_at_mut(chroma_len);
+
+        for y in 0..height {
+            for x in 0..width {
+                let c = frame.pixel(x, y);
+                y_plane[y * width + x] = (0.299 * c.r as f32 + 0.587 * c.g as f32 + 0.114 * c.b as f32).round() as u8;
+            }
+        }
+
+        for cy in 0..chroma_height {
+            for cx in 0..chroma_width {
+                let (mut r, mut g, mut b, mut n) = (0.0, 0.0, 0.0, 0.0);
+                for y in cy * 2..(cy * 2 + 2).min(height) {
+                    for x in cx * 2..(cx * 2 + 2).min(width) {
+                        let c = frame.pixel(x, y);
+                        r += c.r as f32;
+                        g += c.g as f32;
+                        b += c.b as f32;
+                        n += 1.0;
+                    }
+                }
+                let (r, g, b) = (r / n, g / n, b / n);
+                let i = cy * chroma_width + cx;
+                u_plane[i] = (-0.168736 * r - 0.331264 * g + 0.5 * b + 128.0).round().clamp(0.0, 255.0) as u8;
+                v_plane[i] = (0.5 * r - 0.418688 * g - 0.081312 * b + 128.0).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+impl<W: Write> FrameSink for Y4mStream<W> {
+    fn write_frame(&mut self, frame: &Framebuffer, _frame_num: u128) -> io::Result<()> {
+        let size = (frame.width(), frame.height());
+        match self.size {
+            None => {
+                writeln!(self.out, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C420jpeg", size.0, size.1, self.fps)?;
+                self.size = Some(size);
+            },
+            Some(header_size) if header_size != size => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "Y4M frame size differs from the stream header"));
+            },
+            Some(_) => {},
+        }
+
+        self.fill_planes(frame);
+        self.out.write_all(b"FRAME\n")?;
+        self.out.write_all(&self.planes)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn y4m_planes_round_odd_sizes_up() {
+        let mut out = Vec::new();
+        let mut stream = Y4mStream::new(&mut out, 30);
+        let frame = Framebuffer::new(5, 3);
+        stream.write_frame(&frame, 0).unwrap();
+        stream.write_frame(&frame, 1).unwrap();
+        stream.finish().unwrap();
+
+        let header = b"YUV4MPEG2 W5 H3 F30:1 Ip A1:1 C420jpeg\n";
+        // 5x3 luma, 3x2 for each chroma plane.
+        let mut planes = b"FRAME\n".to_vec();
+        planes.extend([0; 15]);
+        planes.extend([128; 12]);
+        let expected = [header.as_slice(), &planes, &planes].concat();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn y4m_rejects_a_frame_of_another_size() {
+        let mut stream = Y4mStream::new(Vec::new(), 30);
+        stream.write_frame(&Framebuffer::new(4, 4), 0).unwrap();
+        assert!(stream.write_frame(&Framebuffer::new(2, 2), 1).is_err());
+    }
+}
@@ -1,29 +1,211 @@
-use std::{iter, ops::Range};
+use std::ops::{Index, IndexMut, Range};
 
-use super::utils::rand_ranged_f32;
+use super::LiveSettings;
+use super::render::Color;
+use super::settings_schema::NamedEnum;
+use super::utils::{LiveRng, rand_chance, rand_normal_f32, rand_ranged_f32};
 
 pub type Gen = f32;
 
+/// Index of a gen column in `Genome`, the position in `GENES`.
+pub type GenId = usize;
+
+pub const LIVE_REGEN_RATE: GenId = 0;
+pub const DIVISION_RATE: GenId = 1;
+pub const PHOTOSYNTH: GenId = 2;
+pub const CARNIVORE: GenId = 3;
+pub const MOVEMENT_FORCE: GenId = 4;
+pub const MOVEMENT_RATE: GenId = 5;
+pub const DEFENCE: GenId = 6;
+pub const ENERGY_DISTRIBUTION: GenId = 7;
+pub const REPULSIVE_FORCE: GenId = 8;
+pub const REPULSIVE_RATE: GenId = 9;
+pub const SAPROPHYTE: GenId = 10;
+pub const MUTATION_RATE: GenId = 11;
+
+/// Description of one gen. Adding a gen is one `GENES` entry (and its `GenId` if the processing uses it),
+/// CSV export, snapshots, normalization and colouring go over the registry.
+pub struct GenInfo {
+    pub name: &'static str,
+    /// New bacteries sample the gen from it, before the normalization.
+    pub default_range: Range<f32>,
+    /// Factor range of a multiplicative mutation, `None` uses `genome_mut_range` of the settings.
+    pub mut_range: Option<Range<f32>>,
+    /// Body colour part, mixed in proportion to the gen. `None` if the gen is not seen.
+    pub color: Option<Color>,
+    /// Part of the genotype: the normalized gens of a bacterium sum to 1.
+    /// Others, like `mutation_rate`, are free values.
+    pub normalized: bool,
+}
+
+pub static GENES: &[GenInfo] = &[
+    GenInfo { name: "live_regen_rate", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "division_rate", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "photosynth", default_range: 0.0..1.0, mut_range: None, color: Some(Color::new(10, 200, 10)), normalized: true },
+    GenInfo { name: "carnivore", default_range: 0.0..1.0, mut_range: None, color: Some(Color::new(200, 10, 10)), normalized: true },
+    GenInfo { name: "movement_force", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "movement_rate", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "defence", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "energy_distribution", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "repulsive_force", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "repulsive_rate", default_range: 0.0..1.0, mut_range: None, color: None, normalized: true },
+    GenInfo { name: "saprophyte", default_range: 0.0..1.0, mut_range: None, color: Some(Color::new(10, 10, 200)), normalized: true },
+    // Strength of all mutations of the children, 1.0 is the strength set by the settings. Mutated itself at full strength.
+    GenInfo { name: "mutation_rate", default_range: 0.5..1.5, mut_range: None, color: None, normalized: false },
+];
+
+pub fn find_gen(name: &str) -> Option<GenId> {
+    GENES.iter().position(|g| g.name == name)
+}
+
+/// How `Genome::mut_clone` changes a gen. Mutated gens never go below 0.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum MutationModel {
+    /// Multiplied by a factor from `genome_mut_range`, a gen at 0 stays 0.
+    #[default]
+    Multiplicative,
+    /// Normal noise with `mutation_sigma` added, the change does not depend on the gen size.
+    Gaussian,
+    /// With `mutation_point_chance` a new value from the gen default range, otherwise unchanged.
+    Point,
+    /// Multiplicative, and with `mutation_jump_chance` also multiplied by a factor from `mutation_jump_range`.
+    Jump,
+}
+
+impl NamedEnum for MutationModel {
+    const ALL: &'static [MutationModel] = &[Self::Multiplicative, Self::Gaussian, Self::Point, Self::Jump];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Multiplicative => "multiplicative",
+            Self::Gaussian => "gaussian",
+            Self::Point => "point",
+            Self::Jump => "jump",
+        }
+    }
+}
+
+/// Mutation parameters of one division, from the settings.
+pub struct Mutation {
+    /// Per `GenId`: `gene_mutation_models` entry or else `mutation_model`.
+    pub models: Vec<MutationModel>,
+    pub factor_range: Range<f32>,
+    pub sigma: f32,
+    pub point_chance: f32,
+    pub jump_chance: f32,
+    pub jump_range: Range<f32>,
+}
+
+impl Mutation {
+    pub fn new(settings: &LiveSettings) -> Mutation {
+        Mutation {
+            models: (0..GENES.len())
+                .map(|id| settings.gene_mutation_models.get(&id).copied().unwrap_or(settings.mutation_model))
+                .collect(),
+            factor_range: settings.genome_mut_range.clone(),
+            sigma: settings.mutation_sigma,
+            point_chance: settings.mutation_point_chance,
+            jump_chance: settings.mutation_jump_chance,
+            jump_range: settings.mutation_jump_range.clone(),
+        }
+    }
+
+    /// Mutated `value` of the gen `id`. `rate` scales the strength: factors move towards 1, noise and chances shrink.
+    pub fn apply(&self, id: GenId, value: Gen, rate: f32, rng: &mut LiveRng) -> Gen {
+        let info = &GENES[id];
+        let factor_range = || info.mut_range.clone().unwrap_or_else(|| self.factor_range.clone());
+        let result = match self.models[id] {
+            MutationModel::Multiplicative => value * scale_factor(rand_ranged_f32(rng, factor_range()), rate),
+            MutationModel::Gaussian => value + rand_normal_f32(rng, self.sigma * rate),
+            MutationModel::Point => match rand_chance(rng, self.point_chance * rate) {
+                true => rand_ranged_f32(rng, info.default_range.clone()),
+                false => value,
+            },
+            MutationModel::Jump => {
+                let value = value * scale_factor(rand_ranged_f32(rng, factor_range()), rate);
+                match rand_chance(rng, self.jump_chance * rate) {
+                    true => value * scale_factor(rand_ranged_f32(rng, self.jump_range.clone()), rate),
+                    false => value,
+                }
+            },
+        };
+        result.max(0.0)
+    }
+}
+
+/// Where the children come from.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum ReproductionMode {
+    /// Division only, the child is a mutated clone.
+    #[default]
+    Asexual,
+    /// Mating of colliding compatible bacteries only, no division.
+    Sexual,
+    /// Both division and mating.
+    Mixed,
+}
+
+impl NamedEnum for ReproductionMode {
+    const ALL: &'static [ReproductionMode] = &[Self::Asexual, Self::Sexual, Self::Mixed];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Asexual => "asexual",
+            Self::Sexual => "sexual",
+            Self::Mixed => "mixed",
+        }
+    }
+}
+
+impl ReproductionMode {
+
+    pub fn division(self) -> bool {
+        self != Self::Sexual
+    }
+
+    pub fn mating(self) -> bool {
+        self != Self::Asexual
+    }
+}
+
+/// How `Genome::crossover` mixes the gens of two parents.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum Crossover {
+    /// Every gen is taken from one parent, each with the chance 0.5.
+    #[default]
+    Uniform,
+    /// Every gen is a random weighted mean of both parents.
+    Blended,
+}
+
+impl NamedEnum for Crossover {
+    const ALL: &'static [Crossover] = &[Self::Uniform, Self::Blended];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Uniform => "uniform",
+            Self::Blended => "blended",
+        }
+    }
+}
+
+/// Mutation `factor` at the mutation `rate`: 1.0 keeps it, 0.0 gives 1.0 (no change), 2.0 doubles the distance from 1.0.
+#[inline(always)]
+pub fn scale_factor(factor: f32, rate: f32) -> f32 {
+    1.0 + (factor - 1.0) * rate
+}
+
+/// Gens of all bacteries, one column per `GENES` entry: `genome[PHOTOSYNTH][i]`.
 #[derive(Default, Clone)]
 pub struct Genome {
     pub length: usize,
-    pub live_regen_rate: Vec<Gen>,
-    pub division_rate: Vec<Gen>,
-    pub photosynth: Vec<Gen>,
-    pub carnivore: Vec<Gen>,
-    pub movement_force: Vec<Gen>,
-    pub movement_rate: Vec<Gen>,
-    pub defence: Vec<Gen>,
-    pub energy_distribution: Vec<Gen>,
-    pub repulsive_force: Vec<Gen>,
-    pub repulsive_rate: Vec<Gen>,
-    pub saprophyte: Vec<Gen>,
+    pub gens: Vec<Vec<Gen>>,
 }
 
 pub trait GenTrait {
     fn get(&self, idx: usize) -> Gen;
-    fn fill_default(&mut self);
-    fn default_one(&mut self, i: usize);
+    fn fill_default(&mut self, range: Range<f32>, rng: &mut LiveRng);
+    fn default_one(&mut self, i: usize, range: Range<f32>, rng: &mut LiveRng);
 }
 
 impl GenTrait for Vec<Gen> {
@@ -33,35 +215,41 @@ impl GenTrait for Vec<Gen> {
     }
 
     #[inline(always)]
-    fn fill_default(&mut self) {
+    fn fill_default(&mut self, range: Range<f32>, rng: &mut LiveRng) {
         for el in self {
-            *el = rand_ranged_f32(0.0..1.0);
+            *el = rand_ranged_f32(rng, range.clone());
         }
     }
 
     #[inline(always)]
-    fn default_one(&mut self, i: usize) {
-        self[i] = rand_ranged_f32(0.0..1.0);
+    fn default_one(&mut self, i: usize, range: Range<f32>, rng: &mut LiveRng) {
+        self[i] = rand_ranged_f32(rng, range);
+    }
+}
+
+impl Index<GenId> for Genome {
+    type Output = Vec<Gen>;
+
+    #[inline(always)]
+    fn index(&self, id: GenId) -> &Vec<Gen> {
+        &self.gens[id]
+    }
+}
+
+impl IndexMut<GenId> for Genome {
+    #[inline(always)]
+    fn index_mut(&mut self, id: GenId) -> &mut Vec<Gen> {
+        &mut self.gens[id]
     }
 }
 
 impl Genome {
-    /// Return new normalized Genome with default gens (random in 0..1)
+    /// Return new normalized Genome with default gens (random in `GenInfo::default_range`)
     #[inline(always)]
-    pub fn new(length: usize) -> Genome {
+    pub fn new(length: usize, rng: &mut LiveRng) -> Genome {
         let mut result = Genome {
             length,
-            live_regen_rate: default_gen(length),
-            division_rate: default_gen(length),
-            photosynth: default_gen(length),
-            carnivore: default_gen(length),
-            movement_force: default_gen(length),
-            movement_rate: default_gen(length),
-            defence: default_gen(length),
-            energy_distribution: default_gen(length),
-            repulsive_force: default_gen(length),
-            repulsive_rate: default_gen(length),
-            saprophyte: default_gen(length),
+            gens: GENES.iter().map(|info| default_gen(length, info.default_range.clone(), rng)).collect(),
         };
 
         result.normilize();
@@ -69,43 +257,72 @@ impl Genome {
     }
 
     #[inline(always)]
-    pub const fn empty() -> Genome {
+    pub fn empty() -> Genome {
         Genome {
             length: 0,
-            live_regen_rate: vec![],
-            division_rate: vec![],
-            photosynth: vec![],
-            carnivore: vec![],
-            movement_force: vec![],
-            movement_rate: vec![],
-            defence: vec![],
-            energy_distribution: vec![],
-            repulsive_force: vec![],
-            repulsive_rate: vec![],
-            saprophyte: vec![],
+            gens: GENES.iter().map(|_| Vec::new()).collect(),
         }
     }
 
     #[inline(always)]
-    pub fn mut_clone(&mut self, from: usize, to: usize, genome_mut_range: Range::<f32>) {
-        for gen in self.iter_mut() {
-            gen[to] = gen[from] * rand_ranged_f32(genome_mut_range.clone());
+    /// The child `to` gets the gens of `from`, mutated at the `mutation_rate` of `from`.
+    pub fn mut_clone(&mut self, from: usize, to: usize, mutation: &Mutation, rng: &mut LiveRng) {
+        let rate = self[MUTATION_RATE][from];
+        for (id, gen) in self.gens.iter_mut().enumerate() {
+            let rate = if id == MUTATION_RATE { 1.0 } else { rate };
+            gen[to] = mutation.apply(id, gen[from], rate, rng);
         }
         self.normilize_one(to);
     }
 
+    /// The child `to` gets the gens of `a` and `b` mixed by `crossover`,
+    /// then mutated at its own inherited `mutation_rate` like in `mut_clone`.
+    pub fn crossover(&mut self, a: usize, b: usize, to: usize, crossover: Crossover, mutation: &Mutation, rng: &mut LiveRng) {
+        for gen in self.gens.iter_mut() {
+            gen[to] = match crossover {
+                Crossover::Uniform => if rand_chance(rng, 0.5) { gen[a] } else { gen[b] },
+                Crossover::Blended => {
+                    let weight = rand_ranged_f32(rng, 0.0..1.0);
+                    gen[a] * weight + gen[b] * (1.0 - weight)
+                },
+            };
+        }
+
+        let rate = self[MUTATION_RATE][to];
+        for (id, gen) in self.gens.iter_mut().enumerate() {
+            let rate = if id == MUTATION_RATE { 1.0 } else { rate };
+            gen[to] = mutation.apply(id, gen[to], rate, rng);
+        }
+        self.normilize_one(to);
+    }
+
+    /// Sum of the absolute differences of the normalized gens of `a` and `b`, 0 for equal genotypes, at most 2.
+    pub fn distance(&self, a: usize, b: usize) -> f32 {
+        self.iter_normalized().map(|gen| (gen[a] - gen[b]).abs()).sum()
+    }
+
     #[inline(always)]
-    pub fn default_one(&mut self, i: usize) {
-        for gen in self.iter_mut() {
-            gen.default_one(i);
+    pub fn default_one(&mut self, i: usize, rng: &mut LiveRng) {
+        for (gen, info) in self.gens.iter_mut().zip(GENES) {
+            gen.default_one(i, info.default_range.clone(), rng);
         }
         self.normilize_one(i);
     }
 
     #[inline(always)]
+    /// Scale the normalized gens of `i` to sum 1.
     pub fn normilize_one(&mut self, i: usize) {
-        let sum = self.iter().map(|v| v[i]).sum::<f32>();
-        for number in self.iter_mut().map(|v| &mut v[i]) {
+        let sum = self.iter_normalized().map(|v| v[i]).sum::<f32>();
+        // Additive and point mutations can zero every gen, such a genome becomes uniform.
+        if sum <= 0.0 {
+            let uniform = 1.0 / GENES.iter().filter(|info| info.normalized).count() as f32;
+            for number in self.iter_normalized_mut().map(|v| &mut v[i]) {
+                *number = uniform;
+            }
+            return;
+        }
+
+        for number in self.iter_normalized_mut().map(|v| &mut v[i]) {
             *number /= sum;
         }
     }
@@ -121,52 +338,147 @@ impl Genome {
         0..self.length
     }
 
+    /// Gen columns in `GENES` order.
     pub fn iter(&self) -> impl Iterator<Item = &Vec<f32>> {
-        assert!(Self::is_iter_correct(create(self).count()), "Check Genome.iter(), is not correct!");
-        return create(self);
-
-        fn create(genome: &Genome) -> impl Iterator<Item = &Vec<f32>> {
-            iter::once(&genome.live_regen_rate)
-                .chain(iter::once(&genome.division_rate))
-                .chain(iter::once(&genome.photosynth))
-                .chain(iter::once(&genome.carnivore))
-                .chain(iter::once(&genome.movement_force))
-                .chain(iter::once(&genome.movement_rate))
-                .chain(iter::once(&genome.defence))
-                .chain(iter::once(&genome.energy_distribution))
-                .chain(iter::once(&genome.repulsive_force))
-                .chain(iter::once(&genome.repulsive_rate))
-                .chain(iter::once(&genome.saprophyte))
-        }
+        self.gens.iter()
     }
 
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Vec<f32>> {
-        assert!(Self::is_iter_correct(create(self).count()), "Check Genome.iter(), is not correct!");
-        return create(self);
-        
-        fn create(genome: &mut Genome) -> impl Iterator<Item = &mut Vec<f32>> {
-            iter::once(&mut genome.live_regen_rate)
-                .chain(iter::once(&mut genome.division_rate))
-                .chain(iter::once(&mut genome.photosynth))
-                .chain(iter::once(&mut genome.carnivore))
-                .chain(iter::once(&mut genome.movement_force))
-                .chain(iter::once(&mut genome.movement_rate))
-                .chain(iter::once(&mut genome.defence))
-                .chain(iter::once(&mut genome.energy_distribution))
-                .chain(iter::once(&mut genome.repulsive_force))
-                .chain(iter::once(&mut genome.repulsive_rate))
-                .chain(iter::once(&mut genome.saprophyte))
-        }
+        self.gens.iter_mut()
+    }
+
+    /// Columns of the gens with `GenInfo::normalized`.
+    pub fn iter_normalized(&self) -> impl Iterator<Item = &Vec<f32>> {
+        self.gens.iter().zip(GENES).filter(|(_, info)| info.normalized).map(|(gen, _)| gen)
     }
 
-    fn is_iter_correct(iter_len: usize) -> bool {
-        std::mem::size_of::<Genome>() ==
-        std::mem::size_of::<Vec<f32>>() * iter_len + std::mem::size_of::<usize>()
+    pub fn iter_normalized_mut(&mut self) -> impl Iterator<Item = &mut Vec<f32>> {
+        self.gens.iter_mut().zip(GENES).filter(|(_, info)| info.normalized).map(|(gen, _)| gen)
     }
 }
 
-pub fn default_gen(length: usize) -> Vec<Gen> {
+pub fn default_gen(length: usize, range: Range<f32>, rng: &mut LiveRng) -> Vec<Gen> {
     let mut res = vec![Gen::default(); length];
-    res.fill_default();
+    res.fill_default(range, rng);
     res
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::save_load::read_settings;
+
+    fn mutation(model: MutationModel) -> Mutation {
+        Mutation {
+            models: vec![model; GENES.len()],
+            factor_range: 0.5..2.0,
+            sigma: 0.1,
+            point_chance: 0.5,
+            jump_chance: 0.5,
+            jump_range: 4.0..8.0,
+        }
+    }
+
+    /// `apply` of `PHOTOSYNTH` at full rate, `count` times from `value`.
+    fn mutated(model: MutationModel, value: Gen, count: usize) -> Vec<Gen> {
+        let mutation = mutation(model);
+        let mut rng = LiveRng::new(7);
+        (0..count).map(|_| mutation.apply(PHOTOSYNTH, value, 1.0, &mut rng)).collect()
+    }
+
+    #[test]
+    fn multiplicative_scales_and_keeps_zero() {
+        let values = mutated(MutationModel::Multiplicative, 0.4, 1000);
+        assert!(values.iter().all(|v| (0.2..0.8).contains(v)));
+        assert!(mutated(MutationModel::Multiplicative, 0.0, 100).iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn gaussian_adds_noise_and_revives_zero() {
+        let values = mutated(MutationModel::Gaussian, 0.4, 1000);
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        assert!((mean - 0.4).abs() < 0.02, "{}", mean);
+        assert!(values.iter().any(|&v| v > 0.5) && values.iter().any(|&v| v < 0.3));
+
+        let values = mutated(MutationModel::Gaussian, 0.0, 100);
+        assert!(values.iter().all(|&v| v >= 0.0));
+        assert!(values.iter().any(|&v| v > 0.0));
+    }
+
+    #[test]
+    fn point_redraws_some_gens_and_revives_zero() {
+        let values = mutated(MutationModel::Point, 0.0, 1000);
+        let redrawn = values.iter().filter(|&&v| v != 0.0).count();
+        assert!((400..600).contains(&redrawn), "{}", redrawn);
+        assert!(values.iter().all(|v| GENES[PHOTOSYNTH].default_range.contains(v)));
+    }
+
+    #[test]
+    fn jump_sometimes_adds_a_big_factor() {
+        let values = mutated(MutationModel::Jump, 0.1, 1000);
+        let jumped = values.iter().filter(|&&v| v >= 0.2).count();
+        assert!((400..600).contains(&jumped), "{}", jumped);
+        assert!(values.iter().all(|v| (0.05..1.6).contains(v)));
+    }
+
+    #[test]
+    fn lower_rate_mutates_less() {
+        let mutation = mutation(MutationModel::Multiplicative);
+        let mut rng = LiveRng::new(7);
+        assert_eq!(mutation.apply(PHOTOSYNTH, 0.4, 0.0, &mut rng), 0.4);
+        assert!((0..100).all(|_| (0.3..0.6).contains(&mutation.apply(PHOTOSYNTH, 0.4, 0.5, &mut rng))));
+    }
+
+    #[test]
+    fn gene_models_override_the_default_model() {
+        let settings = read_settings("mutation_model = \"jump\"\ngene_mutation_models = { carnivore = \"gaussian\" }\n").unwrap();
+        let mutation = Mutation::new(&settings);
+        assert_eq!(mutation.models[CARNIVORE], MutationModel::Gaussian);
+        assert!(mutation.models.iter().enumerate().all(|(id, &m)| id == CARNIVORE || m == MutationModel::Jump));
+        assert_eq!(MutationModel::names(), "multiplicative, gaussian, point or jump");
+    }
+
+    /// Never changes a gen.
+    fn no_mutation() -> Mutation {
+        Mutation { point_chance: 0.0, ..mutation(MutationModel::Point) }
+    }
+
+    /// Parents 0 and 1 with different gens and mutation rates 1 and 2, children in 2 and up.
+    fn parents(children: usize) -> Genome {
+        let mut genome = Genome::new(2 + children, &mut LiveRng::new(5));
+        genome[MUTATION_RATE][0] = 1.0;
+        genome[MUTATION_RATE][1] = 2.0;
+        genome
+    }
+
+    #[test]
+    fn uniform_crossover_takes_each_gen_from_a_parent() {
+        let mut genome = parents(100);
+        let mut rng = LiveRng::new(9);
+        for to in 2..102 {
+            genome.crossover(0, 1, to, Crossover::Uniform, &no_mutation(), &mut rng);
+            let column = |i: usize| genome.iter_normalized().map(|g| g[i]).collect::<Vec<_>>();
+            let (a, b, child) = (column(0), column(1), column(to));
+            // The child is normalized: scaled back by the sum of the taken gens, each is a gen of a parent.
+            let scales = [a[0] / child[0], b[0] / child[0]];
+            let taken = |scale: f32| (0..a.len()).all(|k| (child[k] * scale - a[k]).abs() < 1e-5 || (child[k] * scale - b[k]).abs() < 1e-5);
+            assert!(scales.into_iter().any(taken));
+        }
+        let rates = &genome[MUTATION_RATE][2..];
+        assert!(rates.iter().all(|&r| r == 1.0 || r == 2.0));
+        assert!(rates.contains(&1.0) && rates.contains(&2.0));
+    }
+
+    #[test]
+    fn blended_crossover_mixes_the_parents() {
+        let mut genome = parents(100);
+        let mut rng = LiveRng::new(9);
+        for to in 2..102 {
+            genome.crossover(0, 1, to, Crossover::Blended, &no_mutation(), &mut rng);
+            assert!((genome.iter_normalized().map(|g| g[to]).sum::<f32>() - 1.0).abs() < 1e-5);
+        }
+        let rates = &genome[MUTATION_RATE][2..];
+        assert!(rates.iter().all(|r| (1.0..=2.0).contains(r)));
+        assert!(rates.iter().any(|r| (1.2..1.8).contains(r)));
+    }
+}
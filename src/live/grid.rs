@@ -0,0 +1,162 @@
+use micromath::vector::F32x2;
+
+/// Scalar field over the world in square cells, row by row from the top left corner.
+/// The last row and column may stick out of the world.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct Grid {
+    /// Cell side in pixels.
+    pub cell_size: f32,
+    pub cols: usize,
+    pub rows: usize,
+    pub cells: Vec<f32>,
+}
+
+impl Grid {
+    /// Zero grid covering a `width` x `height` pixels world.
+    pub fn new(width: usize, height: usize, cell_size: f32) -> Grid {
+        let cell_size = cell_size.max(1.0);
+        let cols = (width as f32 / cell_size).ceil().max(1.0) as usize;
+        let rows = (height as f32 / cell_size).ceil().max(1.0) as usize;
+        Grid {
+            cell_size,
+            cols,
+            rows,
+            cells: vec![0.0; cols * rows],
+        }
+    }
+
+    /// Center of the cell `idx` in pixels.
+    pub fn center(&self, idx: usize) -> F32x2 {
+        F32x2 {
+            x: ((idx % self.cols) as f32 + 0.5) * self.cell_size,
+            y: ((idx / self.cols) as f32 + 0.5) * self.cell_size,
+        }
+    }
+
+    /// Cell under `pos`, positions outside the world go to the closest edge cell. `None` for an empty grid.
+    pub fn cell_at(&self, pos: F32x2) -> Option<usize> {
+        if self.cells.is_empty() {
+            return None;
+        }
+
+        let col = ((pos.x / self.cell_size).max(0.0) as usize).min(self.cols - 1);
+        let row = ((pos.y / self.cell_size).max(0.0) as usize).min(self.rows - 1);
+        Some(row * self.cols + col)
+    }
+
+    /// Cells the circle overlaps, at least the cell under its center.
+    pub fn cells_in_circle(&self, pos: F32x2, radius: f32) -> Vec<usize> {
+        let Some(center) = self.cell_at(pos) else {
+            return Vec::new();
+        };
+
+        let size = self.cell_size;
+        let col_range = |x: f32| ((x / size).max(0.0) as usize).min(self.cols - 1);
+        let row_range = |y: f32| ((y / size).max(0.0) as usize).min(self.rows - 1);
+        let mut result = Vec::new();
+        for row in row_range(pos.y - radius)..=row_range(pos.y + radius) {
+            for col in col_range(pos.x - radius)..=col_range(pos.x + radius) {
+                // Closest point of the cell to the circle center.
+                let x = pos.x.clamp(col as f32 * size, (col + 1) as f32 * size);
+                let y = pos.y.clamp(row as f32 * size, (row + 1) as f32 * size);
+                let idx = row * self.cols + col;
+                if idx == center || (x - pos.x).powi(2) + (y - pos.y).powi(2) < radius * radius {
+                    result.push(idx);
+                }
+            }
+        }
+        result
+    }
+
+    /// One explicit diffusion step with closed borders, the total stays the same.
+    /// `amount` is the share exchanged with each neighbour, limited to 0.25 to stay stable.
+    pub fn diffuse(&mut self, amount: f32) {
+        let amount = amount.clamp(0.0, 0.25);
+        if amount == 0.0 {
+            return;
+        }
+
+        let old = self.cells.clone();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let idx = row * self.cols + col;
+                let neighbours = [
+                    (col > 0).then(|| idx - 1),
+                    (col + 1 < self.cols).then(|| idx + 1),
+                    (row > 0).then(|| idx - self.cols),
+                    (row + 1 < self.rows).then(|| idx + self.cols),
+                ];
+                self.cells[idx] += neighbours.iter().flatten().map(|&n| (old[n] - old[idx]) * amount).sum::<f32>();
+            }
+        }
+    }
+
+    pub fn total(&self) -> f32 {
+        self.cells.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(x: f32, y: f32) -> F32x2 {
+        F32x2 { x, y }
+    }
+
+    #[test]
+    fn diffusion_keeps_the_total() {
+        let mut grid = Grid::new(50, 30, 10.0);
+        assert_eq!((grid.cols, grid.rows), (5, 3));
+        grid.cells[0] = 8.0;
+        grid.cells[7] = 4.0;
+        grid.cells[14] = 2.0;
+        for _ in 0..50 {
+            grid.diffuse(0.2);
+            assert!((grid.total() - 14.0).abs() < 1e-4);
+        }
+        // Spread over every cell.
+        assert!(grid.cells.iter().all(|&v| v > 0.0));
+
+        // Too strong diffusion is limited, nothing goes negative.
+        grid.cells.fill(0.0);
+        grid.cells[7] = 1.0;
+        grid.diffuse(10.0);
+        assert!(grid.cells.iter().all(|&v| v >= 0.0));
+        assert_eq!(grid.cells[7], 0.0);
+        assert!((grid.total() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cells_are_found_at_the_edges() {
+        let grid = Grid::new(45, 30, 10.0);
+        assert_eq!((grid.cols, grid.rows), (5, 3));
+        assert_eq!(grid.cell_at(pos(0.0, 0.0)), Some(0));
+        assert_eq!(grid.cell_at(pos(44.9, 29.9)), Some(14));
+        // Outside the world, the closest edge cell.
+        assert_eq!(grid.cell_at(pos(-5.0, 100.0)), Some(10));
+        assert_eq!(grid.cell_at(pos(1000.0, -1.0)), Some(4));
+        assert_eq!(grid.center(6), pos(15.0, 15.0));
+        assert_eq!(Grid::default().cell_at(pos(1.0, 1.0)), None);
+    }
+
+    #[test]
+    fn cells_in_circle_are_clipped_at_the_edges() {
+        let grid = Grid::new(50, 30, 10.0);
+        let mut cells = grid.cells_in_circle(pos(15.0, 15.0), 6.0);
+        cells.sort();
+        assert_eq!(cells, [1, 5, 6, 7, 11]);
+
+        // In a corner, only the cells inside the grid.
+        let mut cells = grid.cells_in_circle(pos(1.0, 1.0), 13.0);
+        cells.sort();
+        assert_eq!(cells, [0, 1, 5, 6]);
+        let mut cells = grid.cells_in_circle(pos(49.0, 29.0), 5.0);
+        cells.sort();
+        assert_eq!(cells, [14]);
+
+        // A tiny circle still has its center cell, one outside the world the closest edge cell.
+        assert_eq!(grid.cells_in_circle(pos(25.0, 15.0), 0.0), [7]);
+        assert_eq!(grid.cells_in_circle(pos(-20.0, 15.0), 3.0), [5]);
+    }
+}
@@ -0,0 +1,127 @@
+use micromath::vector::F32x2;
+
+use super::LiveSettings;
+use super::settings_schema::NamedEnum;
+
+/// Spotlight of the light grid, see `LiveData::update_light`.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub enum LightSpot {
+    /// No spotlight, the light only follows the gradients.
+    #[default]
+    None,
+    /// Spotlight at `light_spot_x`, `light_spot_y`.
+    Fixed,
+    /// Spotlight crossing the world from the left to the right edge once a day, at `light_spot_y`.
+    Sun,
+}
+
+impl NamedEnum for LightSpot {
+    const ALL: &'static [LightSpot] = &[Self::None, Self::Fixed, Self::Sun];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Fixed => "fixed",
+            Self::Sun => "sun",
+        }
+    }
+}
+
+/// Unshaded light multiplier at `pos` of a `width` x `height` world: the gradients and the spotlight.
+pub fn light_factor(settings: &LiveSettings, pos: F32x2, width: f32, height: f32, day_progress: f32) -> f32 {
+    // -1.0 on the left or top edge, 1.0 on the opposite one.
+    let x = pos.x / width * 2.0 - 1.0;
+    let y = pos.y / height * 2.0 - 1.0;
+    let gradient = (1.0 + settings.light_gradient_x * x + settings.light_gradient_y * y).max(0.0);
+
+    let spot_x = match settings.light_spot {
+        LightSpot::None => return gradient,
+        LightSpot::Fixed => settings.light_spot_x,
+        LightSpot::Sun => day_progress,
+    };
+    let dx = pos.x - spot_x * width;
+    let dy = pos.y - settings.light_spot_y * height;
+    let radius = settings.light_spot_radius.max(1.0);
+    gradient * (-(dx * dx + dy * dy) / (radius * radius)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::LiveData;
+    use crate::live::bacteries::Bacteries;
+    use crate::live::utils::LiveRng;
+
+    fn pos(x: f32, y: f32) -> F32x2 {
+        F32x2 { x, y }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn gradients_change_the_light_towards_the_edges() {
+        let mut settings = LiveSettings::new();
+        settings.light_spot = LightSpot::None;
+        settings.light_gradient_x = 0.5;
+        settings.light_gradient_y = -2.0;
+        let factor = |x, y| light_factor(&settings, pos(x, y), 200.0, 100.0, 0.0);
+        assert!(close(factor(100.0, 50.0), 1.0));
+        assert!(close(factor(0.0, 50.0), 0.5));
+        assert!(close(factor(200.0, 50.0), 1.5));
+        assert!(close(factor(100.0, 25.0), 2.0));
+        // Never negative.
+        assert_eq!(factor(100.0, 100.0), 0.0);
+    }
+
+    #[test]
+    fn spotlight_falls_to_1_e_at_its_radius() {
+        let mut settings = LiveSettings::new();
+        settings.light_gradient_x = 0.0;
+        settings.light_gradient_y = 0.0;
+        settings.light_spot = LightSpot::Fixed;
+        settings.light_spot_x = 0.25;
+        settings.light_spot_y = 0.5;
+        settings.light_spot_radius = 20.0;
+        let factor = |x, day_progress| light_factor(&settings, pos(x, 50.0), 200.0, 100.0, day_progress);
+        assert!(close(factor(50.0, 0.0), 1.0));
+        assert!(close(factor(70.0, 0.0), (-1.0f32).exp()));
+        assert!(factor(150.0, 0.0) < 1e-5);
+        // A fixed spot does not move during the day.
+        assert_eq!(factor(50.0, 0.7), factor(50.0, 0.0));
+
+        // The sun crosses from the left edge at the day start to the right edge at its end.
+        settings.light_spot = LightSpot::Sun;
+        let factor = |x, day_progress| light_factor(&settings, pos(x, 50.0), 200.0, 100.0, day_progress);
+        assert!(close(factor(0.0, 0.0), 1.0));
+        assert!(close(factor(100.0, 0.5), 1.0));
+        assert!(factor(100.0, 0.0) < 1e-5);
+    }
+
+    #[test]
+    fn bacteries_shade_others_but_not_themselves() {
+        let mut live = LiveData { light_force: 1.0, ..Default::default() };
+        live.settings = LiveSettings::new();
+        live.settings.light_spot = LightSpot::None;
+        live.settings.light_gradient_x = 0.0;
+        live.settings.light_gradient_y = 0.0;
+        live.settings.light_cell_size = 10.0;
+        live.settings.light_shading = 0.5;
+        live.settings.dead_time = 0.0;
+        live.bacteries = Bacteries::new(4, &mut LiveRng::new(1));
+        // Alone, two in one cell, and a dead one.
+        for (i, (x, y)) in [(15.0, 15.0), (55.0, 55.0), (56.0, 54.0), (85.0, 85.0)].into_iter().enumerate() {
+            live.bacteries.pos[i] = pos(x, y);
+            live.bacteries.radius[i] = 3;
+            live.bacteries.left_time[i] = if i == 3 { 0.0 } else { 1.0 };
+        }
+
+        live.update_light(100, 100, 0.0);
+        assert!(close(live.light_at(0), 1.0));
+        assert!(close(live.light_at(1), 0.5));
+        assert!(close(live.light_at(2), 0.5));
+        assert_eq!(live.light.cells[live.light.cell_at(pos(85.0, 85.0)).unwrap()], 1.0);
+        assert_eq!(live.light.cells[live.light.cell_at(pos(55.0, 55.0)).unwrap()], 0.25);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::settings_schema::NamedEnum;
+
+/// Birth of one bacterium, see `Bacteries::id`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineageRecord {
+    /// 0 for spawned bacteries. Mated children follow their first parent, a tree has no place for the mate.
+    pub parent_id: u64,
+    pub birth_tick: u128,
+    pub generation: u32,
+}
+
+/// Branch length unit of `Lineage::newick`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BranchLength {
+    Generations,
+    /// Simulated seconds.
+    Time,
+}
+
+impl NamedEnum for BranchLength {
+    const ALL: &'static [BranchLength] = &[Self::Generations, Self::Time];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Generations => "generations",
+            Self::Time => "time",
+        }
+    }
+}
+
+/// Births of the living bacteries and of all their ancestors, by id.
+/// Extinct branches are dropped from time to time by `prune`.
+#[derive(Default, Clone, Debug)]
+pub struct Lineage {
+    pub records: BTreeMap<u64, LineageRecord>,
+    /// Record count that asks for the next `prune`.
+    pub prune_at: usize,
+}
+
+/// Node of the tree written by `Lineage::newick`.
+struct TreeNode {
+    /// Id of a living bacterium or of a branching ancestor.
+    label: Option<u64>,
+    depth: f64,
+    parent: usize,
+    children: Vec<usize>,
+}
+
+impl Lineage {
+    pub fn record(&mut self, id: u64, record: LineageRecord) {
+        self.records.insert(id, record);
+    }
+
+    pub fn needs_prune(&self) -> bool {
+        self.records.len() > self.prune_at
+    }
+
+    /// Keep only the records of `living` and of their ancestors.
+    pub fn prune(&mut self, living: &[u64], capacity: usize) {
+        let kept = self.ancestry(living);
+        self.records.retain(|id, _| kept.contains(id));
+        self.prune_at = (self.records.len() * 2).max(capacity * 4);
+    }
+
+    /// `ids` and all their recorded ancestors.
+    fn ancestry(&self, ids: &[u64]) -> BTreeSet<u64> {
+        let mut result = BTreeSet::new();
+        for &id in ids {
+            let mut id = id;
+            while self.records.contains_key(&id) && result.insert(id) {
+                id = self.records[&id].parent_id;
+            }
+        }
+        result
+    }
+
+    /// Ancestry tree of the `living` bacteries in Newick format, ending with `;`.
+    /// Leaves are the living bacteries, inner nodes are named by the ancestor they split from and sit at its birth.
+    /// Ancestors with one surviving line are left out, their branches are joined.
+    /// `now_tick` places the living leaves in `Time` mode, `time_step` converts ticks to seconds.
+    pub fn newick(&self, living: &[u64], mode: BranchLength, now_tick: u128, time_step: f32) -> String {
+        let kept = self.ancestry(living);
+        let living = living.iter().copied().collect::<BTreeSet<u64>>();
+        let mut children = BTreeMap::<u64, Vec<u64>>::new();
+        let mut roots = Vec::new();
+        for &id in kept.iter() {
+            match self.records[&id].parent_id {
+                parent if kept.contains(&parent) => children.entry(parent).or_default().push(id),
+                _ => roots.push(id),
+            }
+        }
+
+        let depth = |id: u64| match mode {
+            BranchLength::Generations => self.records[&id].generation as f64,
+            BranchLength::Time => self.records[&id].birth_tick as f64 * time_step as f64,
+        };
+        let leaf_depth = |id: u64| match mode {
+            BranchLength::Generations => self.records[&id].generation as f64,
+            BranchLength::Time => now_tick as f64 * time_step as f64,
+        };
+
+        fn add(tree: &mut Vec<TreeNode>, parent: usize, label: Option<u64>, depth: f64) -> usize {
+            tree.push(TreeNode { label, depth, parent, children: Vec::new() });
+            let idx = tree.len() - 1;
+            tree[parent].children.push(idx);
+            idx
+        }
+
+        // Built without recursion, lines of thousands of generations would overflow the stack.
+        let mut tree = vec![TreeNode { label: None, depth: 0.0, parent: 0, children: Vec::new() }];
+        let mut stack = roots.iter().rev().map(|&id| (id, 0)).collect::<Vec<(u64, usize)>>();
+        while let Some((id, parent)) = stack.pop() {
+            let kids = children.get(&id).map(|k| k.as_slice()).unwrap_or_default();
+            let alive = living.contains(&id);
+            match (alive, kids.len()) {
+                (false, 0) => {},
+                (false, 1) => stack.push((kids[0], parent)),
+                (true, 0) => { add(&mut tree, parent, Some(id), leaf_depth(id)); },
+                _ => {
+                    let node = add(&mut tree, parent, (!alive).then_some(id), depth(id));
+                    if alive {
+                        add(&mut tree, node, Some(id), leaf_depth(id));
+                    }
+                    stack.extend(kids.iter().rev().map(|&kid| (kid, node)));
+                },
+            }
+        }
+
+        // A single line starts at its oldest ancestor instead of the artificial root.
+        let root = match tree[0].children.as_slice() {
+            [single] => *single,
+            _ => 0,
+        };
+        write_newick(&tree, root, mode)
+    }
+}
+
+fn write_newick(tree: &[TreeNode], root: usize, mode: BranchLength) -> String {
+    let mut result = String::new();
+    // Node and the number of its children already written.
+    let mut stack = vec![(root, 0)];
+    while let Some((idx, written)) = stack.pop() {
+        let node = &tree[idx];
+        if written < node.children.len() {
+            result.push(match written {
+                0 => '(',
+                _ => ',',
+            });
+            stack.push((idx, written + 1));
+            stack.push((node.children[written], 0));
+            continue;
+        }
+
+        if !node.children.is_empty() {
+            result.push(')');
+        }
+        if let Some(label) = node.label {
+            write!(result, "{}", label).unwrap();
+        }
+        if idx != root {
+            let length = node.depth - tree[node.parent].depth;
+            match mode {
+                BranchLength::Generations => write!(result, ":{}", length).unwrap(),
+                BranchLength::Time => write!(result, ":{:.3}", length).unwrap(),
+            }
+        }
+    }
+    result.push(';');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 ─┬─ 2 ─┬─ 4
+    ///    │     └─ 5
+    ///    └─ 3 ─── 6
+    fn lineage() -> Lineage {
+        let mut lineage = Lineage::default();
+        for (id, parent_id, birth_tick, generation) in [(1, 0, 0, 0), (2, 1, 10, 1), (3, 1, 15, 1), (4, 2, 20, 2), (5, 2, 30, 2), (6, 3, 25, 2)] {
+            lineage.record(id, LineageRecord { parent_id, birth_tick, generation });
+        }
+        lineage
+    }
+
+    #[test]
+    fn newick_in_generations_joins_single_lines() {
+        let newick = lineage().newick(&[4, 5, 6], BranchLength::Generations, 40, 0.5);
+        assert_eq!(newick, "((4:1,5:1)2:1,6:2)1;");
+    }
+
+    #[test]
+    fn newick_in_time_ends_at_now() {
+        let newick = lineage().newick(&[4, 5, 6], BranchLength::Time, 40, 0.5);
+        assert_eq!(newick, "((4:15.000,5:15.000)2:5.000,6:20.000)1;");
+    }
+
+    #[test]
+    fn newick_keeps_living_ancestors_as_tips() {
+        assert_eq!(lineage().newick(&[2, 4], BranchLength::Generations, 40, 0.5), "(2:0,4:1);");
+    }
+
+    #[test]
+    fn newick_of_separate_roots_has_a_common_root() {
+        let mut lineage = lineage();
+        lineage.record(7, LineageRecord { parent_id: 0, birth_tick: 0, generation: 0 });
+        assert_eq!(lineage.newick(&[4, 7], BranchLength::Generations, 40, 0.5), "(4:2,7:0);");
+    }
+
+    #[test]
+    fn prune_keeps_living_and_their_ancestors() {
+        let mut lineage = lineage();
+        lineage.prune(&[6], 1);
+        assert_eq!(lineage.records.keys().copied().collect::<Vec<u64>>(), [1, 3, 6]);
+    }
+}
@@ -1,5 +1,8 @@
 use rapier2d::{prelude::*, na::Vector2};
 
+/// `user_data` of the world edge colliders, bacteries have their index.
+pub const EDGE_USER_DATA: u128 = u128::MAX;
+
 #[derive(Default)]
 pub struct PhysicsData {
     gravity: Vector2<f32>,
@@ -13,45 +16,7 @@ pub struct PhysicsData {
     multibody_joints: MultibodyJointSet,
     ccd_solver: CCDSolver,
     hooks: (),
-    pub events: CollisionHandler,
-}
-
-#[derive(Default, Debug)]
-pub struct CollisionHandler {
-    pub collisions: Option<Vec<CollisionEvent>>,
-}
-
-impl EventHandler for CollisionHandler {
-    fn handle_collision_event(
-        &self,
-        _bodies: &RigidBodySet,
-        _colliders: &ColliderSet,
-        event: CollisionEvent,
-        _contact_pair: Option<&ContactPair>,
-    ) {
-        let ptr = self as *const CollisionHandler as *mut CollisionHandler;
-        unsafe {
-            match &mut (*ptr).collisions {
-                Some(vec) => {
-                    vec.push(event);
-                }
-                None => {
-                    (*ptr).collisions = Some(vec![event]);
-                }
-            }
-        }
-    }
-
-    fn handle_contact_force_event(
-        &self,
-        _dt: Real,
-        _bodies: &RigidBodySet,
-        _colliders: &ColliderSet,
-        _contact_pair: &ContactPair,
-        _total_force_magnitude: Real,
-    ) {
-        todo!()
-    }
+    events: (),
 }
 
 impl PhysicsData {
@@ -70,12 +35,63 @@ impl PhysicsData {
     pub fn get_coll_mut(&mut self, handle: ColliderHandle) -> &mut Collider {
         self.colliders.get_mut(handle).unwrap()
     }
+
+    /// Touching bacteries after the last step as `(a, b)` slots with `a < b`, sorted.
+    /// Built from the current contacts only, so a world restored from a snapshot gets the same list.
+    pub fn contacts(&self) -> Vec<(usize, usize)> {
+        let mut result = Vec::new();
+        for pair in self.narrow_phase.contact_pairs().filter(|p| p.has_any_active_contact) {
+            let a = self.colliders[pair.collider1].user_data;
+            let b = self.colliders[pair.collider2].user_data;
+            if a == EDGE_USER_DATA || b == EDGE_USER_DATA {
+                continue;
+            }
+            result.push((a.min(b) as usize, a.max(b) as usize));
+        }
+        result.sort_unstable();
+        result
+    }
+
+    /// Complete physics state, contact manifolds and solver warm start included, so a restored world steps the same.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(&(
+            &self.gravity,
+            &self.integration_parameters,
+            &self.islands,
+            &self.broad_phase,
+            &self.narrow_phase,
+            &self.bodies,
+            &self.colliders,
+            &self.impulse_joints,
+            &self.multibody_joints,
+            &self.ccd_solver,
+        )).unwrap()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> bincode::Result<PhysicsData> {
+        let (gravity, integration_parameters, islands, broad_phase, narrow_phase, bodies, colliders, impulse_joints, multibody_joints, ccd_solver) =
+            bincode::deserialize(bytes)?;
+        Ok(PhysicsData {
+            gravity,
+            integration_parameters,
+            islands,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            impulse_joints,
+            multibody_joints,
+            ccd_solver,
+            hooks: (),
+            events: (),
+        })
+    }
 }
 
-pub fn create_pipeline(rigidbody_set: RigidBodySet, collider_set: ColliderSet) -> PhysicsData {
+pub fn create_pipeline(rigidbody_set: RigidBodySet, collider_set: ColliderSet, dt: f32) -> PhysicsData {
     PhysicsData {
         gravity: Vector2::new(0.0, 0.0),
-        integration_parameters: IntegrationParameters::default(),
+        integration_parameters: IntegrationParameters { dt, ..IntegrationParameters::default() },
         islands: IslandManager::new(),
         broad_phase: BroadPhase::new(),
         narrow_phase: NarrowPhase::new(),
@@ -85,7 +101,7 @@ pub fn create_pipeline(rigidbody_set: RigidBodySet, collider_set: ColliderSet) -
         multibody_joints: MultibodyJointSet::new(),
         ccd_solver: CCDSolver::new(),
         hooks: (),
-        events: CollisionHandler { collisions: Some(Vec::new()) },
+        events: (),
     }
 }
 
@@ -124,6 +140,6 @@ pub fn create_edges(width: f32, height: f32, edge_width: f32, edge_height: f32,
             rb_handle: RigidBodyHandle,
             set: &mut RigidBodySet) {
         let pos = Isometry::new(vector![x, y], 0.0);
-        collider_set.insert_with_parent(ColliderBuilder::cuboid(sx, sy).position(pos).build(), rb_handle, set);
+        collider_set.insert_with_parent(ColliderBuilder::cuboid(sx, sy).position(pos).user_data(EDGE_USER_DATA).build(), rb_handle, set);
     }
 }
\ No newline at end of file
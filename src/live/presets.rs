@@ -0,0 +1,48 @@
+/// Built-in settings layer, applied on top of `LiveSettings::new()` and under the settings file.
+#[derive(Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// TOML settings text, only the keys that differ from the defaults.
+    pub settings: &'static str,
+}
+
+pub fn find_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.name == name)
+}
+
+pub static PRESETS: &[Preset] = &[
+    Preset {
+        name: "predator_heavy",
+        description: "Cheap and harmful hunting, plants grow slower and defend more.",
+        settings: "
+carnivore_rate = 12.0
+carnivore_damage = 25.0
+carnivore_cost = 15.0
+defence = 20.0
+photosynth_rate = 0.015
+",
+    },
+    Preset {
+        name: "calm_photosynth",
+        description: "Bright days and weak carnivores, the world fills up with plants.",
+        settings: "
+morning_light_force = 0.8
+photosynth_rate = 0.03
+carnivore_rate = 6.0
+carnivore_damage = 5.0
+max_repulsive_force = 150.0
+",
+    },
+    Preset {
+        name: "fast_evolution",
+        description: "Strong mutations, cheap divisions and short lives.",
+        settings: "
+genome_mut_range = [0.8, 1.25]
+radius_mut_range = [0.85, 1.15]
+division_energy = 6.0
+max_alive = 60.0
+start_alive_range = [1.0, 60.0]
+",
+    },
+];
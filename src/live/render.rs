@@ -0,0 +1,149 @@
+use std::f32::consts::PI;
+use std::ops::Range;
+
+use super::app::AppData;
+use super::bacteries::Bacteries;
+use super::genome::{GENES, MOVEMENT_FORCE, MOVEMENT_RATE};
+
+pub mod framebuffer;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    #[inline(always)]
+    pub const fn new(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+}
+
+/// Drawing surface for the paint code. Coordinates are surface pixels, origin is the top left corner.
+pub trait Renderer {
+    /// Surface (width, height) in pixels.
+    fn size(&self) -> (usize, usize);
+    fn clear(&mut self, color: Color);
+    fn fill_circle(&mut self, x: i32, y: i32, radius: i32, color: Color);
+    fn polyline(&mut self, points: &[(i32, i32)], color: Color);
+    /// `x`, `y` is the top left corner of the text.
+    fn text(&mut self, x: i32, y: i32, text: &str, color: Color);
+}
+
+const DAY_COLORS: [Color; 6] = [
+    Color::new(26,43,69),
+    Color::new(26,43,69),
+    Color::new(204,150,74),
+    Color::new(206,200,110),
+    Color::new(206,200,110),
+    Color::new(26,43,69),
+];
+
+const FLAGELLA_COLOR: Color = Color::new(0, 0, 0);
+
+/// Paint the world scaled to the renderer size.
+pub fn paint_frame<R: Renderer>(renderer: &mut R, app: &AppData) {
+    renderer.clear(interpolate_colors(&DAY_COLORS, app.day_progress));
+    paint_bacteries(renderer, app);
+}
+
+fn paint_bacteries<R: Renderer>(renderer: &mut R, app: &AppData) {
+    let dead_time = app.live_data.settings.dead_time;
+    let bac = &app.live_data.bacteries;
+    let (sx, sy, sr) = world_scale(renderer, app);
+
+    // Gens with a colour, e.g. red carnivore, green photosynth and blue saprophyte.
+    let colored = GENES.iter().enumerate().filter_map(|(id, info)| Some((id, info.color?))).collect::<Vec<_>>();
+    let colors = colored.iter().map(|(_, color)| *color).collect::<Vec<Color>>();
+    let mut proportions = vec![0.0; colored.len()];
+
+//    let settings = &app.live_data.settings;
+//    paint_flagella(renderer, bac, settings.flagella_num_range.clone(), settings.flagella_len_range.clone(), dead_time, sr);
+
+    for i in bac.into_iter() {
+        if bac.is_alive(i, dead_time) {
+            for (proportion, (id, _)) in proportions.iter_mut().zip(colored.iter()) {
+                *proportion = bac.genome[*id][i];
+            }
+            let color = mix_colors(&colors, &proportions);
+            let pos = bac.pos[i];
+            let radius = (bac.radius[i] as f32 * sr).round().max(1.0) as i32;
+            renderer.fill_circle((pos.x * sx) as i32, (pos.y * sy) as i32, radius, color);
+        }
+    }
+}
+
+/// `scale` is the world to surface scale.
+#[inline(always)]
+pub fn paint_flagella<R: Renderer>(renderer: &mut R, bac: &Bacteries, num_range: Range<i32>, len_range: Range<i32>, dead_time: f32, scale: f32) {
+    for i in bac.into_iter() {
+        if bac.is_alive(i, dead_time) {
+            let len = (len_range.start as f32 + (len_range.end - len_range.start) as f32 * bac.genome[MOVEMENT_FORCE][i]).round();
+            let mut num_flagella = (num_range.start as f32 + (num_range.end - num_range.start) as f32 * bac.genome[MOVEMENT_RATE][i]).round() as u32;
+            if num_flagella % 2 == 1 && num_flagella > 0 {
+                num_flagella -= 1;
+            }
+
+            if num_flagella == 0 {
+                continue;
+            }
+
+            num_flagella /= 2;
+
+            let r = (bac.radius[i] as f32 + len) * scale;
+            let c = bac.pos[i] * scale;
+
+            for i in 0..num_flagella as i32 {
+                let angle = 2.0 * PI * (i as f32) / num_flagella as f32;
+                let back_angle = angle + PI;
+                let x1 = (c.x + r * angle.cos()) as i32;
+                let y1 = (c.y + r * angle.sin()) as i32;
+                let x2 = (c.x + r * back_angle.cos()) as i32;
+                let y2 = (c.y + r * back_angle.sin()) as i32;
+                renderer.polyline(&[(x1, y1), (x2, y2)], FLAGELLA_COLOR);
+            }
+        }
+    }
+}
+
+/// World to surface scale: (x, y, radius).
+fn world_scale<R: Renderer>(renderer: &R, app: &AppData) -> (f32, f32, f32) {
+    let (width, height) = renderer.size();
+    let sx = width as f32 / app.width as f32;
+    let sy = height as f32 / app.height as f32;
+    (sx, sy, sx.min(sy))
+}
+
+/// Linear interpolation over evenly spaced `colors`, `weight` in 0..=1.
+pub fn interpolate_colors(colors: &[Color], weight: f32) -> Color {
+    let segment = 1.0 / (colors.len() - 1) as f32;
+    let index1 = ((weight / segment).floor() as usize).min(colors.len() - 2);
+    let index2 = index1 + 1;
+
+    let color1 = colors[index1];
+    let color2 = colors[index2];
+    let segment_weight = (weight - index1 as f32 * segment) / segment;
+    let lerp = |a: u8, b: u8| ((1.0 - segment_weight) * a as f32 + segment_weight * b as f32) as u8;
+
+    Color::new(lerp(color1.r, color2.r), lerp(color1.g, color2.g), lerp(color1.b, color2.b))
+}
+
+pub fn mix_colors(colors: &[Color], proportions: &[f32]) -> Color {
+    let total_prop = proportions.iter().sum::<f32>();
+
+    let calculate_component = |component: fn(&Color) -> u8| {
+        colors
+            .iter()
+            .map(|v| component(v) as f32)
+            .zip(proportions)
+            .fold(0.0, |acc, (v, p)| acc + v * p) / total_prop
+    };
+
+    let r = calculate_component(|v| v.r);
+    let g = calculate_component(|v| v.g);
+    let b = calculate_component(|v| v.b);
+
+    Color::new(r as u8, g as u8, b as u8)
+}
@@ -0,0 +1,258 @@
+use super::{Color, Renderer};
+
+/// In-memory RGBA8 surface, rows top to bottom. Frames can be produced without any GPU or window.
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// RGBA8 bytes, `width * height * 4` long.
+    #[inline(always)]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline(always)]
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let i = (y * self.width + x) * 4;
+        Color::new(self.pixels[i], self.pixels[i + 1], self.pixels[i + 2])
+    }
+
+    #[inline(always)]
+    fn put(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+
+        let i = (y as usize * self.width + x as usize) * 4;
+        self.pixels[i..i + 4].copy_from_slice(&[color.r, color.g, color.b, 255]);
+    }
+
+    #[inline(always)]
+    fn hline(&mut self, x0: i32, x1: i32, y: i32, color: Color) {
+        if y < 0 || y as usize >= self.height {
+            return;
+        }
+
+        let x0 = x0.max(0);
+        let x1 = x1.min(self.width as i32 - 1);
+        for x in x0..=x1 {
+            self.put(x, y, color);
+        }
+    }
+
+    fn line(&mut self, from: (i32, i32), to: (i32, i32), color: Color) {
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.put(x, y, color);
+            if x == to.0 && y == to.1 {
+                break;
+            }
+
+            let err2 = 2 * err;
+            if err2 >= dy {
+                err += dy;
+                x += step_x;
+            }
+            if err2 <= dx {
+                err += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Text pixel size, so captions stay readable on big frames.
+    fn text_scale(&self) -> i32 {
+        (self.height as i32 / 360).max(1)
+    }
+}
+
+impl Renderer for Framebuffer {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn clear(&mut self, color: Color) {
+        for px in self.pixels.chunks_exact_mut(4) {
+            px.copy_from_slice(&[color.r, color.g, color.b, 255]);
+        }
+    }
+
+    fn fill_circle(&mut self, x: i32, y: i32, radius: i32, color: Color) {
+        for dy in -radius..=radius {
+            let half = ((radius * radius - dy * dy) as f32).sqrt() as i32;
+            self.hline(x - half, x + half, y + dy, color);
+        }
+    }
+
+    fn polyline(&mut self, points: &[(i32, i32)], color: Color) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], color);
+        }
+    }
+
+    fn text(&mut self, x: i32, y: i32, text: &str, color: Color) {
+        let scale = self.text_scale();
+        let mut cursor = x;
+        for c in text.chars() {
+            let glyph = glyph(c);
+            for row in 0..GLYPH_HEIGHT {
+                for col in 0..GLYPH_WIDTH {
+                    let bit = (GLYPH_HEIGHT - 1 - row) * GLYPH_WIDTH + (GLYPH_WIDTH - 1 - col);
+                    if glyph >> bit & 1 == 0 {
+                        continue;
+                    }
+
+                    for sy in 0..scale {
+                        for sx in 0..scale {
+                            self.put(cursor + col * scale + sx, y + row * scale + sy, color);
+                        }
+                    }
+                }
+            }
+            cursor += (GLYPH_WIDTH + 1) * scale;
+        }
+    }
+}
+
+const GLYPH_WIDTH: i32 = 3;
+const GLYPH_HEIGHT: i32 = 5;
+
+/// 3x5 bitmap font, rows top to bottom, 3 bits per row with the left pixel first.
+fn glyph(c: char) -> u16 {
+    match c.to_ascii_uppercase() {
+        '0' => 0b111_101_101_101_111,
+        '1' => 0b010_110_010_010_111,
+        '2' => 0b111_001_111_100_111,
+        '3' => 0b111_001_111_001_111,
+        '4' => 0b101_101_111_001_001,
+        '5' => 0b111_100_111_001_111,
+        '6' => 0b111_100_111_101_111,
+        '7' => 0b111_001_001_001_001,
+        '8' => 0b111_101_111_101_111,
+        '9' => 0b111_101_111_001_111,
+        'A' => 0b010_101_111_101_101,
+        'B' => 0b110_101_110_101_110,
+        'C' => 0b011_100_100_100_011,
+        'D' => 0b110_101_101_101_110,
+        'E' => 0b111_100_110_100_111,
+        'F' => 0b111_100_110_100_100,
+        'G' => 0b011_100_101_101_011,
+        'H' => 0b101_101_111_101_101,
+        'I' => 0b111_010_010_010_111,
+        'J' => 0b001_001_001_101_010,
+        'K' => 0b101_101_110_101_101,
+        'L' => 0b100_100_100_100_111,
+        'M' => 0b101_111_111_101_101,
+        'N' => 0b110_101_101_101_101,
+        'O' => 0b010_101_101_101_010,
+        'P' => 0b110_101_110_100_100,
+        'Q' => 0b010_101_101_110_011,
+        'R' => 0b110_101_110_101_101,
+        'S' => 0b011_100_010_001_110,
+        'T' => 0b111_010_010_010_010,
+        'U' => 0b101_101_101_101_111,
+        'V' => 0b101_101_101_101_010,
+        'W' => 0b101_101_111_111_101,
+        'X' => 0b101_101_010_101_101,
+        'Y' => 0b101_101_010_010_010,
+        'Z' => 0b111_001_010_100_111,
+        ' ' => 0,
+        '.' => 0b000_000_000_000_010,
+        ',' => 0b000_000_000_010_100,
+        ':' => 0b000_010_000_010_000,
+        '-' => 0b000_000_111_000_000,
+        '=' => 0b000_111_000_111_000,
+        '/' => 0b001_001_010_100_100,
+        '%' => 0b101_001_010_100_101,
+        '(' => 0b001_010_010_010_001,
+        ')' => 0b100_010_010_010_100,
+        _ => 0b111_001_010_000_010, // '?'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color::new(255, 0, 0);
+
+    /// Painted pixels as (x, y), row by row.
+    fn painted(fb: &Framebuffer) -> Vec<(usize, usize)> {
+        (0..fb.height())
+            .flat_map(|y| (0..fb.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| fb.pixels()[(y * fb.width() + x) * 4 + 3] != 0)
+            .collect()
+    }
+
+    #[test]
+    fn fill_circle_paints_the_disc() {
+        let mut fb = Framebuffer::new(11, 11);
+        fb.fill_circle(5, 5, 2, RED);
+        let rows = [(5, 5), (4, 6), (3, 7), (4, 6), (5, 5)];
+        let expected = rows.iter().enumerate()
+            .flat_map(|(dy, &(x0, x1))| (x0..=x1).map(move |x| (x, dy + 3)))
+            .collect::<Vec<_>>();
+        assert_eq!(painted(&fb), expected);
+        assert_eq!(fb.pixel(5, 5), RED);
+    }
+
+    #[test]
+    fn fill_circle_is_clipped_at_the_edges() {
+        let mut fb = Framebuffer::new(10, 10);
+        fb.fill_circle(0, 0, 3, RED);
+        assert_eq!(painted(&fb).len(), 4 + 3 + 3 + 1);
+        fb.fill_circle(9, 9, 3, RED);
+        assert_eq!(painted(&fb).len(), 2 * (4 + 3 + 3 + 1));
+
+        let mut fb = Framebuffer::new(10, 10);
+        fb.fill_circle(-10, 5, 3, RED);
+        fb.fill_circle(5, 20, 3, RED);
+        assert!(painted(&fb).is_empty());
+    }
+
+    #[test]
+    fn polyline_joins_the_points() {
+        let mut fb = Framebuffer::new(10, 10);
+        fb.polyline(&[(1, 1), (4, 1), (4, 3), (6, 5)], RED);
+        let expected = [(1, 1), (2, 1), (3, 1), (4, 1), (4, 2), (4, 3), (5, 4), (6, 5)];
+        assert_eq!(painted(&fb), expected);
+    }
+
+    #[test]
+    fn polyline_is_clipped_at_the_edges() {
+        let mut fb = Framebuffer::new(10, 10);
+        fb.polyline(&[(-5, 2), (14, 2)], RED);
+        assert_eq!(painted(&fb), (0..10).map(|x| (x, 2)).collect::<Vec<_>>());
+
+        let mut fb = Framebuffer::new(10, 10);
+        fb.polyline(&[(-5, -5), (-1, 20), (20, 30)], RED);
+        assert!(painted(&fb).is_empty());
+    }
+}
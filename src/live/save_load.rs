@@ -1,87 +1,163 @@
-use std::fmt::Display;
-use std::fs::File;
-use std::io::{prelude::*, BufReader};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Display};
+use std::fs::{self, File};
+use std::io::prelude::*;
 use std::ops::Range;
-use std::path::Path;
-use std::str::FromStr;
-
-use super::{app::AppData, LiveSettings};
-
-const DEFAULT_SETTINGS_FILE : &str =
-"move_force 	   		        100.0
-energy_for_move             0.5
-vel_range 					-1.0..1.0
-radius_range 				8..20
-max_alive 					100.0
-start_alive_range 			1.0..100.0
-dead_time 					0.0
-start_energy 				1.0
-division_energy 			10.0
-alive_to_energy_coef 		0.1
-photosynth_rate 			0.02
-carnivore_rate 				10.0
-carnivore_damage 			15.0
-defence 					15.0
-carnivore_cost 				20.0
-genome_mut_range 			0.9..1.1
-radius_mut_range 			0.9..1.1
-flagella_num_range 			6..14
-flagella_len_range 			2..8
-max_energy_distribution 	10.0
-max_repulsive_force 		300.0
-night_light_force           0.2
-morning_light_force         0.65
-day_light_force             1.0
-day_length_sec              480.0
-on_dead_matter              0.5
-saprophyte_rate             1.0";
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use toml::{Spanned, Value};
+
+use super::{app::AppData, genome::GENES, simulation::Simulation, LiveSettings};
+use super::presets::{find_preset, Preset, PRESETS};
+use super::settings_schema::{default_settings_file, find_setting, validate_settings, SettingValue, SETTINGS};
+
+/// Settings file in the current directory.
+pub const SETTINGS_FILE: &str = "bac_settings.toml";
+/// Settings file of the versions before TOML, it is not read anymore.
+const LEGACY_SETTINGS_FILE: &str = "bac_settings.txt";
+
+/// Real time between modification checks of a watched settings file.
+const SETTINGS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Key of a settings file naming the file it is based on, relative to its own directory.
+const EXTENDS_KEY: &str = "extends";
+/// `extends = "preset:<name>"` bases a settings file on a built-in preset instead of a file.
+const PRESET_PREFIX: &str = "preset:";
+
+/// Environment variables `LIVE_<KEY>` override settings, e.g. `LIVE_PHOTOSYNTH_RATE=0.05`.
+const ENV_PREFIX: &str = "LIVE_";
+
+/// A settings problem, points at the source (file, `--set`, variable), line and key when it can.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsError {
+    pub source: Option<String>,
+    /// 1-based.
+    pub line: Option<usize>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(source) = &self.source {
+            write!(f, "{}:", source)?;
+        }
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+        }
+        if self.source.is_some() || self.line.is_some() {
+            write!(f, " ")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{}: ", key)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// One `key=value` setting on top of the settings file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsOverride {
+    /// Where it comes from for error messages, e.g. `--set` or the variable name.
+    pub source: String,
+    pub key: String,
+    /// TOML value, ranges can also be written as `start..end`.
+    pub value: String,
+}
+
+impl SettingsOverride {
+    /// `key=value` of a `--set` argument.
+    pub fn parse_arg(arg: &str) -> Result<SettingsOverride, String> {
+        match arg.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => Ok(SettingsOverride {
+                source: "--set".to_string(),
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+            }),
+            _ => Err(format!("expected key=value, found {}", arg)),
+        }
+    }
+
+    /// `LIVE_<KEY>` variables of the process environment, sorted by name.
+    /// Other `LIVE_` variables are none of ours and are skipped.
+    pub fn from_env() -> Vec<SettingsOverride> {
+        let mut result = std::env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+                find_setting(&key)?;
+                Some(SettingsOverride { source: name, key, value })
+            })
+            .collect::<Vec<SettingsOverride>>();
+        result.sort_by(|a, b| a.source.cmp(&b.source));
+        result
+    }
+
+    fn toml_value(&self) -> Value {
+        if let Some((start, end)) = self.value.split_once("..") {
+            if let (Some(start), Some(end)) = (parse_toml_value(start), parse_toml_value(end)) {
+                return Value::Array(vec![start, end]);
+            }
+        }
+
+        // Not a TOML value, keep the text so the error names what was given.
+        parse_toml_value(&self.value).unwrap_or_else(|| Value::String(self.value.clone()))
+    }
+}
+
+fn parse_toml_value(text: &str) -> Option<Value> {
+    toml::from_str::<BTreeMap<String, Value>>(&format!("value = {}", text.trim()))
+        .ok()?
+        .remove("value")
+}
 
 pub fn try_save(app: &AppData) -> std::io::Result<()> {
-    if app.frame_num % 1000 == 0 {
-        let path = format!("{}/bacteries_data_{}.csv", std::env::current_dir().unwrap().display(), app.frame_num);
-        let file = File::create(path).unwrap();
-        let mut writer = csv::Writer::from_writer(file);
-
-        let genome = &app.live_data.bacteries.genome;
-        let headers = ["live_regen_rate",
-                       "division_rate",
-                       "photosynth",
-                       "carnivore",
-                       "movement_force",
-                       "movement_rate",
-                       "defence",
-                       "energy_distribution",
-                       "repulsive_force",
-                       "repulsive_rate",
-                       "saprophyte"];
-        writer.write_record(&headers)?;
-        for i in 0..genome.length {
-            let row = [genome.live_regen_rate[i].to_string(),
-                       genome.division_rate[i].to_string(),
-                       genome.photosynth[i].to_string(),
-                       genome.carnivore[i].to_string(),
-                       genome.movement_force[i].to_string(),
-                       genome.movement_rate[i].to_string(),
-                       genome.defence[i].to_string(),
-                       genome.energy_distribution[i].to_string(),
-                       genome.repulsive_force[i].to_string(),
-                       genome.repulsive_rate[i].to_string(),
-                        genome.saprophyte[i].to_string(),
-                       ];
-            writer.write_record(&row)?;
-        }
-
-        writer.flush()?;
+    if app.frame_num.is_multiple_of(1000) {
+        let path = std::env::current_dir()?.join(format!("bacteries_data_{}.csv", app.frame_num));
+        write_csv(app, &path)?;
     }
     
     Ok(())
 }
 
-pub fn create_default_settings_file(path: &String) {
-    match File::create(path.clone()) {
+/// One row per living bacterium: lineage and species columns, then the gens in `GENES` order.
+pub fn write_csv(app: &AppData, path: &Path) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(File::create(path)?);
+
+    let bac = &app.live_data.bacteries;
+    let dead_time = app.live_data.settings.dead_time;
+    let lineage = ["id", "parent_id", "mate_id", "generation", "birth_tick", "species"];
+    writer.write_record(lineage.into_iter().chain(GENES.iter().map(|g| g.name)))?;
+    for i in bac.into_iter().filter(|i| bac.is_alive(*i, dead_time)) {
+        let lineage = [bac.id[i].to_string(), bac.parent_id[i].to_string(), bac.mate_id[i].to_string(),
+                       bac.generation[i].to_string(), bac.birth_tick[i].to_string(), bac.species[i].to_string()];
+        writer.write_record(lineage.into_iter().chain(bac.genome.iter().map(|gen| gen[i].to_string())))?;
+    }
+
+    writer.flush()
+}
+
+/// One row per species since the start: id, birth and extinction tick (empty while living), sizes and the representative gens.
+pub fn write_species_csv(app: &AppData, path: &Path) -> std::io::Result<()> {
+    let mut writer = csv::Writer::from_writer(File::create(path)?);
+
+    let columns = ["id", "born_tick", "extinct_tick", "size", "peak_size"];
+    writer.write_record(columns.into_iter().chain(GENES.iter().map(|g| g.name)))?;
+    for species in app.live_data.species.species.iter() {
+        let columns = [species.id.to_string(), species.born_tick.to_string(),
+                       species.extinct_tick.map(|t| t.to_string()).unwrap_or_default(),
+                       species.size.to_string(), species.peak_size.to_string()];
+        writer.write_record(columns.into_iter().chain(species.representative.iter().map(|v| v.to_string())))?;
+    }
+
+    writer.flush()
+}
+
+pub fn create_default_settings_file(path: &Path) {
+    match File::create(path) {
         Ok(mut f) => {
-            match f.write_all(DEFAULT_SETTINGS_FILE.as_bytes()) {
-                Ok(_) => { println!("Default settings file created ({})", path); },
+            match f.write_all(default_settings_file().as_bytes()) {
+                Ok(_) => { eprintln!("Default settings file created ({})", path.display()); },
                 Err(e) => log_err(e),
             }
         },
@@ -93,161 +169,490 @@ pub fn create_default_settings_file(path: &String) {
     }
 }
 
-pub fn load_settings() -> LiveSettings {
-    let path = format!("{}/bac_settings.txt", std::env::current_dir().unwrap().display());
-    if !Path::new(path.as_str()).exists(){
-        create_default_settings_file(&path);
+/// `bac_settings.toml` in the current directory.
+pub fn default_settings_path() -> PathBuf {
+    std::env::current_dir().unwrap().join(SETTINGS_FILE)
+}
+
+/// Read `bac_settings.toml` from the current directory with the `LIVE_<KEY>` environment overrides,
+/// the default file is created if it is missing.
+pub fn load_settings() -> Result<LiveSettings, Vec<SettingsError>> {
+    SettingsSource::new(None, Vec::new()).load()
+}
+
+/// Everything the settings are built from, later layers win: `LiveSettings::new()`, the preset, the preset
+/// the settings file chain `extends`, the files the settings file `extends` (base first), the settings file, `LIVE_<KEY>` environment variables, then the given overrides.
+#[derive(Debug, Clone)]
+pub struct SettingsSource {
+    pub path: PathBuf,
+    pub preset: Option<&'static Preset>,
+    pub overrides: Vec<SettingsOverride>,
+}
+
+impl SettingsSource {
+    /// `path` is `bac_settings.toml` in the current directory if `None`.
+    pub fn new(path: Option<&Path>, overrides: Vec<SettingsOverride>) -> SettingsSource {
+        let mut all_overrides = SettingsOverride::from_env();
+        all_overrides.extend(overrides);
+        SettingsSource {
+            path: path.map(Path::to_path_buf).unwrap_or_else(default_settings_path),
+            preset: None,
+            overrides: all_overrides,
+        }
     }
-    let file = File::open(path);
-    let mut result = LiveSettings::new();
-    let mut night_light_force = result.light_force[0];
-    let mut morning_light_force = result.light_force[1];
-    let mut day_light_force = result.light_force[2];
-    let mut floats = [
-        ("move_force", &mut result.move_force),
-        ("energy_for_move", &mut result.energy_for_move),
-
-        ("max_alive", &mut result.max_alive),
-        ("dead_time", &mut result.dead_time),
-
-        ("start_energy", &mut result.start_energy),
-        ("division_energy", &mut result.division_energy),
-        ("alive_to_energy_coef", &mut result.alive_to_energy_coef),
-
-        ("photosynth_rate", &mut result.photosynth_rate),
-        ("carnivore_rate", &mut result.carnivore_rate),
-        ("carnivore_damage", &mut result.carnivore_damage),
-        ("defence", &mut result.defence),
-        ("carnivore_cost", &mut result.carnivore_cost),
-
-        ("max_energy_distribution", &mut result.max_energy_distribution),
-
-        ("max_repulsive_force", &mut result.max_repulsive_force),
-
-        ("night_light_force", &mut night_light_force),
-        ("morning_light_force", &mut morning_light_force),
-        ("day_light_force", &mut day_light_force),
-        ("day_length_sec", &mut result.day_length_sec),
-
-        ("on_dead_matter", &mut result.on_dead_matter),
-        ("saprophyte_rate", &mut result.saprophyte_rate),
-    ];
-
-    let mut ranges_f32 = [
-        ("vel_range", &mut result.vel_range),
-
-        ("start_alive_range", &mut result.start_alive_range),
-
-        ("genome_mut_range", &mut result.genome_mut_range),
-        ("radius_mut_range", &mut result.radius_mut_range),
-    ];
-
-    let mut ranges_i32 = [
-        ("radius_range", &mut result.radius_range),
-
-        ("flagella_num_range", &mut result.flagella_num_range),
-        ("flagella_len_range", &mut result.flagella_len_range),
-    ];
-
-    match file {
-        Ok(file) => {
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                if line.is_err() { continue; }
-                let line = line.unwrap();
-                read_floats(&mut floats.iter_mut(), &line);
-                read_ranges::<f32>(&mut ranges_f32.iter_mut(), &line);
-                read_ranges::<i32>(&mut ranges_i32.iter_mut(), &line);
+
+    /// Read and print the settings. The default file is created if it is the missing one.
+    pub fn load(&self) -> Result<LiveSettings, Vec<SettingsError>> {
+        if !self.path.exists() && self.path == default_settings_path() {
+            if self.path.with_file_name(LEGACY_SETTINGS_FILE).exists() {
+                eprintln!("{} is not read anymore, move your values to {}", LEGACY_SETTINGS_FILE, SETTINGS_FILE);
             }
+            create_default_settings_file(&self.path);
+        }
+
+        let result = self.read()?;
+        match self.preset {
+            Some(preset) => eprintln!("LiveSettings read with preset {}:", preset.name),
+            None => eprintln!("LiveSettings read with:"),
+        }
+        eprintln!("{}", result);
+        Ok(result)
+    }
+
+    pub fn read(&self) -> Result<LiveSettings, Vec<SettingsError>> {
+        let mut files = Vec::new();
+        let base_preset = read_chain(&self.path, &mut files).map_err(|e| vec![e])?;
+
+        let mut layers = Vec::new();
+        for preset in self.preset.into_iter().chain(base_preset) {
+            layers.push((Some(format!("preset {}", preset.name)), preset.settings.to_string()));
+        }
+        layers.extend(files.into_iter().rev().map(|(path, text)| (Some(path.display().to_string()), text)));
+        read_layers(&layers, &self.overrides)
+    }
+
+    /// The settings file and the files it extends, as far as they can be read.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let _ = read_chain(&self.path, &mut files);
+        files.into_iter().map(|(path, _)| path).collect()
+    }
+}
+
+/// Read `path` and then the files it extends into `files`, the base file last.
+/// Returns the preset the base file extends, if any.
+fn read_chain(path: &Path, files: &mut Vec<(PathBuf, String)>) -> Result<Option<&'static Preset>, SettingsError> {
+    let source = Some(path.display().to_string());
+    let same_file = |other: &Path| match (fs::canonicalize(path), fs::canonicalize(other)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => path == other,
+    };
+    if files.iter().any(|(other, _)| same_file(other)) {
+        return Err(SettingsError {
+            source,
+            line: None,
+            key: Some(EXTENDS_KEY.to_string()),
+            message: "the files extend each other in a loop".to_string(),
+        });
+    }
+
+    let text = fs::read_to_string(path).map_err(|e| SettingsError {
+        source: source.clone(),
+        line: None,
+        key: None,
+        message: format!("can`t read the file with error: {}", e),
+    })?;
+
+    // A broken file is reported by `read_layers` with the rest of its errors.
+    let base = match toml::from_str::<BTreeMap<Spanned<String>, Spanned<Value>>>(&text) {
+        Ok(table) => match table.iter().find(|(key, _)| key.get_ref() == EXTENDS_KEY) {
+            Some((_, value)) => match value.get_ref() {
+                Value::String(base) => Some((base.clone(), line_of(&text, value.span().start))),
+                other => return Err(SettingsError {
+                    source,
+                    line: Some(line_of(&text, value.span().start)),
+                    key: Some(EXTENDS_KEY.to_string()),
+                    message: format!("expected a file path or \"{}<name>\", found {}", PRESET_PREFIX, describe(other)),
+                }),
+            },
+            None => None,
         },
-        Err(err) => eprintln!("Can`t open settings file with error: {}", err),
-    }
-
-    result.light_force = [
-        night_light_force,
-        morning_light_force,
-        day_light_force,
-        night_light_force,
-    ];
-
-    println!("LiveSettings read with:");
-    println!("{}", result);
-    result
-}
-
-fn read_floats(floats: &mut std::slice::IterMut<(&str, &mut f32)>, line: &String) {
-    for (name, field) in floats {
-        if line.contains(*name) {
-            let value = line
-                            .chars()
-                            .filter(is_settings_valid_char)
-                            .collect::<String>()
-                            .parse::<f32>();
-            match value {
-                Ok(v) => {
-                    **field = v;
-                },
-                Err(e) => eprintln!("Can`t parse settings {} with error: {}", name, e),
-            }
+        Err(_) => None,
+    };
+
+    files.push((path.to_path_buf(), text));
+    let Some((base, line)) = base else {
+        return Ok(None);
+    };
+    let Some(name) = base.strip_prefix(PRESET_PREFIX) else {
+        return read_chain(&path.parent().unwrap_or(Path::new("")).join(base), files);
+    };
+
+    find_preset(name).map(Some).ok_or_else(|| {
+        let names = PRESETS.iter().map(|p| p.name).collect::<Vec<&str>>();
+        SettingsError {
+            source,
+            line: Some(line),
+            key: Some(EXTENDS_KEY.to_string()),
+            message: format!("unknown preset {}, the presets are {}", name, names.join(", ")),
+        }
+    })
+}
+
+/// Watches a settings file by polling its modification time, so edits apply without a restart.
+pub struct SettingsWatcher {
+    source: SettingsSource,
+    /// Of the settings file and the files it extends.
+    modified: Vec<Option<SystemTime>>,
+    last_poll: Instant,
+}
+
+impl SettingsWatcher {
+    /// The current file version counts as already applied. Overrides of `source` are applied on every reload.
+    pub fn new(source: SettingsSource) -> SettingsWatcher {
+        SettingsWatcher {
+            modified: modified_times(&source),
+            source,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// The settings read again if the file or a file it extends has changed since the last poll.
+    /// Checks the file at most once per `SETTINGS_POLL_INTERVAL`, so it can be called every tick.
+    pub fn poll(&mut self) -> Option<Result<LiveSettings, Vec<SettingsError>>> {
+        if self.last_poll.elapsed() < SETTINGS_POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified_times(&self.source);
+        if modified.iter().all(Option::is_none) || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(self.source.read())
+    }
+
+    /// Poll and apply changed settings to `simulation`, reporting errors and settings that need a restart.
+    pub fn update(&mut self, simulation: &mut Simulation) {
+        if let Some(result) = self.poll() {
+            self.apply(result, simulation);
+        }
+    }
+
+    /// Read and apply the settings now, changed or not. A world resumed from a snapshot runs with the settings
+    /// of its last run, this brings in the edits made since then.
+    pub fn apply_now(&mut self, simulation: &mut Simulation) {
+        self.modified = modified_times(&self.source);
+        self.apply(self.source.read(), simulation);
+    }
+
+    fn apply(&self, result: Result<LiveSettings, Vec<SettingsError>>, simulation: &mut Simulation) {
+        match result {
+            Ok(settings) => {
+                eprintln!("Settings reloaded from {}", self.source.path.display());
+                for name in simulation.apply_settings(settings) {
+                    eprintln!("{} can`t change while the world runs, it applies to a new world only", name);
+                }
+            },
+            Err(errors) => {
+                eprintln!("Settings not reloaded, the current ones are kept:");
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+            },
         }
     }
 }
 
-fn read_ranges<T: FromStr + Default + Display + Copy>(ranges: &mut std::slice::IterMut<(&str, &mut std::ops::Range<T>)>, line: &String)
-    where <T as FromStr>::Err: std::fmt::Display
-{
-    for (name, field) in ranges {
-        if line.contains(*name) {
-            let split = line
-                            .chars()
-                            .filter(is_settings_valid_char)
-                            .collect::<String>();
-            let split = split.split("..");
+fn modified_times(source: &SettingsSource) -> Vec<Option<SystemTime>> {
+    source.files().iter().map(|path| path.metadata().and_then(|m| m.modified()).ok()).collect()
+}
 
-            if split.clone().count() < 2 {
-                eprintln!("Can`t parse settings {}, range split is incorrect", name);
+/// Parse TOML settings text, missing keys keep `LiveSettings::new()` values.
+/// Every bad or unknown key is reported, the result is only returned if there are none.
+pub fn read_settings(text: &str) -> Result<LiveSettings, Vec<SettingsError>> {
+    read_layers(&[(None, text.to_string())], &[])
+}
+
+/// Settings `layers` as (source, TOML text) in order, then `overrides`, then validation.
+fn read_layers(layers: &[(Option<String>, String)], overrides: &[SettingsOverride]) -> Result<LiveSettings, Vec<SettingsError>> {
+    let mut result = LiveSettings::new();
+    let mut errors = Vec::new();
+    // Where the final value of each key comes from, for validation errors.
+    let mut origins = HashMap::new();
+
+    for (source, text) in layers {
+        let table = match toml::from_str::<BTreeMap<Spanned<String>, Spanned<Value>>>(text) {
+            Ok(table) => table,
+            Err(e) => {
+                errors.push(SettingsError {
+                    source: source.clone(),
+                    line: e.span().map(|span| line_of(text, span.start)),
+                    key: None,
+                    message: e.message().to_string(),
+                });
+                continue;
+            },
+        };
+
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_str();
+            if name == EXTENDS_KEY {
                 continue;
             }
 
-            let range = read_range_split::<T>(&mut split.clone(), &name);
-            (*field).start = range.start;
-            (*field).end = range.end;
+            let line = Some(line_of(text, key.span().start));
+            origins.insert(name.to_string(), (source.clone(), line));
+            if let Err(message) = set_setting(&mut result, name, value.get_ref()) {
+                errors.push(SettingsError {
+                    source: source.clone(),
+                    line,
+                    key: Some(name.to_string()),
+                    message,
+                });
+            }
         }
     }
+
+    for o in overrides {
+        origins.insert(o.key.clone(), (Some(o.source.clone()), None));
+        if let Err(message) = set_setting(&mut result, &o.key, &o.toml_value()) {
+            errors.push(SettingsError {
+                source: Some(o.source.clone()),
+                line: None,
+                key: Some(o.key.clone()),
+                message,
+            });
+        }
+    }
+
+    for (name, message) in validate_settings(&result) {
+        let (source, line) = origins.get(name).cloned().unwrap_or((None, None));
+        errors.push(SettingsError {
+            source,
+            line,
+            key: Some(name.to_string()),
+            message,
+        });
+    }
+
+    // Layer by layer, then by line. Overrides are not layers and come last.
+    errors.sort_by_key(|e| (layers.iter().position(|(source, _)| *source == e.source).unwrap_or(layers.len()), e.line));
+    match errors.is_empty() {
+        true => Ok(result),
+        false => Err(errors),
+    }
 }
 
-fn read_range_split<T: FromStr + Default>(split: &mut std::str::Split<&str>, field_name: &str) -> Range<T>
-    where <T as FromStr>::Err: std::fmt::Display
-{
-    return Range {
-        start: read(split, field_name),
-        end: read(split, field_name),
+/// Set the setting called `name` from a TOML value.
+fn set_setting(settings: &mut LiveSettings, name: &str, value: &Value) -> Result<(), String> {
+    let setting = find_setting(name).ok_or_else(|| "unknown key".to_string())?;
+    let value = match setting.default {
+        SettingValue::Float(_) => SettingValue::Float(read_f32(value)?),
+        SettingValue::Int(_) => SettingValue::Int(read_u64(value)?),
+        SettingValue::FloatRange(_) => SettingValue::FloatRange(read_range(value, read_f32)?),
+        SettingValue::IntRange(_) => SettingValue::IntRange(read_range(value, read_i32)?),
+        SettingValue::Text(_) => SettingValue::Text(read_string(value)?.into()),
+        SettingValue::Table(_) => SettingValue::Table(read_table(value)?),
     };
+    setting.set(settings, value)
+}
 
-    #[inline(always)]
-    fn read<T: FromStr + Default>(split: &mut std::str::Split<&str>, field_name: &str) -> T
-        where <T as FromStr>::Err: std::fmt::Display
-    {
-        match split.nth(0) {
-            Some(s) => {
-                match s.parse::<T>() {
-                    Ok(v) => {
-                        return v
-                    },
-                    Err(e) => {
-                        eprintln!("Can`t parse settings {} with error: {}", field_name, e);
-                    }
-                };
-            },
-            None => {
-                eprintln!("Can`t parse settings {}, range split is incorrect", field_name);
-            },
-        }
+/// `settings` as TOML settings text, `read_settings` gives them back exactly.
+pub fn write_settings(settings: &LiveSettings) -> String {
+    SETTINGS.iter()
+        .map(|setting| format!("{} = {}\n", setting.name, setting.get(settings)))
+        .collect()
+}
 
-        Default::default()
+fn read_f32(value: &Value) -> Result<f32, String> {
+    match value {
+        Value::Float(v) => Ok(*v as f32),
+        Value::Integer(v) => Ok(*v as f32),
+        _ => Err(format!("expected a number, found {}", describe(value))),
     }
 }
 
-fn is_settings_valid_char(c: &char) -> bool {
-    c.is_numeric() || *c == '.' || *c == ',' || *c == '-'
-}
\ No newline at end of file
+fn read_i32(value: &Value) -> Result<i32, String> {
+    match value {
+        Value::Integer(v) => i32::try_from(*v).map_err(|_| format!("{} is out of the 32-bit integer range", v)),
+        _ => Err(format!("expected an integer, found {}", describe(value))),
+    }
+}
+
+/// Values over `i64::MAX` don`t fit a TOML integer, they are written as strings.
+fn read_u64(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Integer(v) => u64::try_from(*v).map_err(|_| format!("expected a non-negative integer, found {}", v)),
+        Value::String(v) => v.parse::<u64>().map_err(|_| format!("expected a non-negative integer, found {}", describe(value))),
+        _ => Err(format!("expected a non-negative integer, found {}", describe(value))),
+    }
+}
+
+fn read_string(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(v) => Ok(v.clone()),
+        _ => Err(format!("expected a string, found {}", describe(value))),
+    }
+}
+
+fn read_table(value: &Value) -> Result<BTreeMap<String, String>, String> {
+    match value {
+        Value::Table(v) => v.iter()
+            .map(|(key, value)| Ok((key.clone(), read_string(value).map_err(|e| format!("{}: {}", key, e))?)))
+            .collect(),
+        _ => Err(format!("expected a table, found {}", describe(value))),
+    }
+}
+
+fn read_range<T>(value: &Value, read: fn(&Value) -> Result<T, String>) -> Result<Range<T>, String> {
+    match value {
+        Value::Array(v) if v.len() == 2 => Ok(read(&v[0])?..read(&v[1])?),
+        _ => Err(format!("expected a [start, end] array, found {}", describe(value))),
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(v) => format!("an array of {} values", v.len()),
+        Value::Table(_) => "a table".to_string(),
+        _ => format!("{} {}", value.type_str(), value),
+    }
+}
+
+/// 1-based line of the byte `offset`.
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(text: &str) -> Vec<(Option<usize>, Option<String>, String)> {
+        read_settings(text).unwrap_err().into_iter().map(|e| (e.line, e.key, e.message)).collect()
+    }
+
+    #[test]
+    fn missing_keys_keep_defaults() {
+        let settings = read_settings("carnivore_damage = 20\nradius_range = [6, 18]\n").unwrap();
+        assert_eq!(settings.carnivore_damage, 20.0);
+        assert_eq!(settings.radius_range, 6..18);
+        assert_eq!(settings.time_step, LiveSettings::new().time_step);
+    }
+
+    #[test]
+    fn written_settings_read_back() {
+        let mut settings = LiveSettings::new();
+        settings.seed = 12345;
+        settings.light_gradient_x = 0.1;
+        let text = write_settings(&settings);
+        assert_eq!(write_settings(&read_settings(&text).unwrap()), text);
+    }
+
+    #[test]
+    fn every_bad_key_is_reported_with_its_line() {
+        let found = errors("time_step = 0.01\nfoo = 1\ncarnivore_damage = \"x\"\n");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0], (Some(2), Some("foo".to_string()), "unknown key".to_string()));
+        assert_eq!((found[1].0, found[1].1.as_deref()), (Some(3), Some("carnivore_damage")));
+    }
+
+    #[test]
+    fn invalid_values_are_reported_with_their_line() {
+        let found = errors("seed = 1\ntime_step = 0.0\nmating_chance = 2.0\n");
+        let keys = found.iter().map(|(line, key, _)| (*line, key.as_deref())).collect::<Vec<_>>();
+        assert_eq!(keys, [(Some(2), Some("time_step")), (Some(3), Some("mating_chance"))]);
+    }
+
+    #[test]
+    fn syntax_errors_have_a_line_and_no_key() {
+        let found = errors("seed = 1\ntime_step = \n");
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0, found[0].1.as_deref()), (Some(2), None));
+    }
+
+    #[test]
+    fn overrides_win_over_the_file() {
+        let overrides = [SettingsOverride::parse_arg("radius_range=4..9").unwrap()];
+        let settings = read_layers(&[(None, "radius_range = [6, 18]".to_string())], &overrides).unwrap();
+        assert_eq!(settings.radius_range, 4..9);
+    }
+
+    /// Empty directory for the settings files of one test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("live_{}_test_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn extended_files_are_read_base_first() {
+        let dir = test_dir("extends");
+        fs::create_dir_all(dir.join("base")).unwrap();
+        fs::write(dir.join("base/base.toml"), "seed = 1\nmax_alive = 10\ndead_time = 10\n").unwrap();
+        fs::write(dir.join("base/middle.toml"), "extends = \"base.toml\"\nmax_alive = 20\ndead_time = 20\n").unwrap();
+        fs::write(dir.join("top.toml"), "extends = \"base/middle.toml\"\ndead_time = 30\n").unwrap();
+
+        let source = SettingsSource::new(Some(&dir.join("top.toml")), Vec::new());
+        let settings = source.read().unwrap();
+        assert_eq!((settings.seed, settings.max_alive, settings.dead_time), (1, 20.0, 30.0));
+        let names = source.files().iter().map(|f| f.file_name().unwrap().to_owned()).collect::<Vec<_>>();
+        assert_eq!(names, ["top.toml", "middle.toml", "base.toml"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_extends_loop_is_an_error() {
+        let dir = test_dir("extends_loop");
+        fs::write(dir.join("a.toml"), "extends = \"b.toml\"\n").unwrap();
+        fs::write(dir.join("b.toml"), "extends = \"./a.toml\"\n").unwrap();
+
+        let errors = SettingsSource::new(Some(&dir.join("a.toml")), Vec::new()).read().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key.as_deref(), Some(EXTENDS_KEY));
+        assert!(errors[0].message.contains("loop"), "{}", errors[0].message);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn presets_lie_under_the_files() {
+        let dir = test_dir("preset_layers");
+        let path = dir.join("settings.toml");
+        // fast_evolution sets division_energy and max_alive, predator_heavy carnivore_damage and defence.
+        fs::write(&path, "max_alive = 90\ndefence = 1\n").unwrap();
+        let mut source = SettingsSource::new(Some(&path), vec![SettingsOverride::parse_arg("division_energy=8").unwrap()]);
+        source.preset = find_preset("fast_evolution");
+        let settings = source.read().unwrap();
+        assert_eq!((settings.division_energy, settings.max_alive), (8.0, 90.0));
+        assert_eq!(settings.radius_mut_range, 0.85..1.15);
+
+        // The preset the file extends lies over the one of `--preset`.
+        fs::write(&path, "extends = \"preset:predator_heavy\"\nmax_alive = 90\ndefence = 1\n").unwrap();
+        source.overrides.clear();
+        let settings = source.read().unwrap();
+        assert_eq!((settings.division_energy, settings.max_alive), (6.0, 90.0));
+        assert_eq!((settings.carnivore_damage, settings.defence), (25.0, 1.0));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_file_can_extend_a_preset() {
+        let dir = test_dir("preset");
+        let path = dir.join("settings.toml");
+        let source = SettingsSource::new(Some(&path), Vec::new());
+
+        fs::write(&path, "extends = \"preset:fast_evolution\"\nmax_alive = 90\n").unwrap();
+        let settings = source.read().unwrap();
+        assert_eq!(settings.division_energy, 6.0);
+        assert_eq!(settings.max_alive, 90.0);
+        assert_eq!(source.files(), std::slice::from_ref(&path));
+
+        fs::write(&path, "max_alive = 90\nextends = \"preset:slow\"\n").unwrap();
+        let errors = source.read().unwrap_err();
+        assert_eq!((errors[0].line, errors[0].key.as_deref()), (Some(2), Some(EXTENDS_KEY)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
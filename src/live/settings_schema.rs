@@ -0,0 +1,1090 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::mem::discriminant;
+use std::ops::Range;
+
+use super::LiveSettings;
+use super::genome::{find_gen, Crossover, MutationModel, ReproductionMode, GENES};
+use super::light::LightSpot;
+
+/// Value of one setting, the variant is the setting type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingValue {
+    Float(f32),
+    Int(u64),
+    FloatRange(Range<f32>),
+    IntRange(Range<i32>),
+    Text(Cow<'static, str>),
+    Table(BTreeMap<String, String>),
+}
+
+use SettingValue::{Float, FloatRange, Int, IntRange, Table, Text};
+
+impl SettingValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Float(_) => "number",
+            Int(_) => "non-negative integer",
+            FloatRange(_) => "[number, number]",
+            IntRange(_) => "[integer, integer]",
+            Text(_) => "string",
+            Table(_) => "table of strings",
+        }
+    }
+
+    // Only called by `set` of the schema entries after the type check in `Setting::set`.
+    fn float(self) -> f32 {
+        match self { Float(v) => v, _ => unreachable!() }
+    }
+
+    fn int(self) -> u64 {
+        match self { Int(v) => v, _ => unreachable!() }
+    }
+
+    fn float_range(self) -> Range<f32> {
+        match self { FloatRange(v) => v, _ => unreachable!() }
+    }
+
+    fn int_range(self) -> Range<i32> {
+        match self { IntRange(v) => v, _ => unreachable!() }
+    }
+
+    fn text(self) -> String {
+        match self { Text(v) => v.into_owned(), _ => unreachable!() }
+    }
+
+    fn table(self) -> BTreeMap<String, String> {
+        match self { Table(v) => v, _ => unreachable!() }
+    }
+
+    // Also after the check in `Setting::set`, so the name is valid.
+    fn named<T: NamedEnum>(self) -> T {
+        T::from_name(&self.text()).unwrap()
+    }
+}
+
+/// Enum given by name in the settings or on the command line, parsed once when it is set.
+pub trait NamedEnum: Copy + 'static {
+    const ALL: &'static [Self];
+
+    fn name(self) -> &'static str;
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|v| v.name() == name)
+    }
+
+    /// All names in words, e.g. `uniform or blended`.
+    fn names() -> String {
+        let names: Vec<&str> = Self::ALL.iter().map(|v| v.name()).collect();
+        match names.split_last() {
+            Some((last, rest)) if !rest.is_empty() => format!("{} or {}", rest.join(", "), last),
+            _ => names.concat(),
+        }
+    }
+}
+
+/// TOML text of the value. `{:?}` is the shortest exact f32 text and always a TOML float,
+/// integers over `i64::MAX` don`t fit a TOML integer and are written as strings.
+impl Display for SettingValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Float(v) => write!(f, "{:?}", v),
+            Int(v) if i64::try_from(*v).is_ok() => write!(f, "{}", v),
+            Int(v) => write!(f, "\"{}\"", v),
+            FloatRange(v) => write!(f, "[{:?}, {:?}]", v.start, v.end),
+            IntRange(v) => write!(f, "[{}, {}]", v.start, v.end),
+            Text(v) => write!(f, "{}", toml::Value::String(v.to_string())),
+            Table(v) if v.is_empty() => write!(f, "{{}}"),
+            Table(v) => {
+                let entries = v.iter()
+                    .map(|(key, value)| format!("{} = {}", key, toml::Value::String(value.clone())))
+                    .collect::<Vec<String>>();
+                write!(f, "{{ {} }}", entries.join(", "))
+            },
+        }
+    }
+}
+
+/// Lower bound of a value or of a range start.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Limit {
+    None,
+    AtLeast(f64),
+    Above(f64),
+}
+
+/// Validation beyond `Limit`, e.g. names or a probability.
+pub struct Check {
+    /// Valid values in words.
+    pub valid: &'static str,
+    /// The problem with the value, if any.
+    pub check: fn(&SettingValue) -> Option<String>,
+}
+
+/// Description of one `LiveSettings` field. The settings file, `Display`, validation and `--explain` are built from it.
+pub struct Setting {
+    /// Key in the settings file.
+    pub name: &'static str,
+    /// Heading the key is listed under in the default file.
+    pub section: &'static str,
+    pub default: SettingValue,
+    /// Empty if the value has no unit.
+    pub unit: &'static str,
+    pub limit: Limit,
+    /// Ranges only: sampled with `gen_range`, so `start < end`. Otherwise `start <= end` is enough.
+    pub sampled: bool,
+    /// Fixed for the world lifetime, a running world keeps its value.
+    pub restart_only: bool,
+    pub check: Option<Check>,
+    pub description: &'static str,
+    get: fn(&LiveSettings) -> SettingValue,
+    set: fn(&mut LiveSettings, SettingValue),
+}
+
+impl Setting {
+    pub fn get(&self, settings: &LiveSettings) -> SettingValue {
+        (self.get)(settings)
+    }
+
+    /// Fails if `value` is not of the setting type or its `check` rejects it, limits are checked by `validate`.
+    pub fn set(&self, settings: &mut LiveSettings, value: SettingValue) -> Result<(), String> {
+        if discriminant(&value) != discriminant(&self.default) {
+            return Err(format!("expected {}", self.default.type_name()));
+        }
+        if let Some(message) = self.check.as_ref().and_then(|check| (check.check)(&value)) {
+            return Err(message);
+        }
+
+        (self.set)(settings, value);
+        // The night light both starts and ends the day.
+        settings.light_force[3] = settings.light_force[0];
+        Ok(())
+    }
+
+    /// The problem with the current value in `settings`, if any.
+    pub fn validate(&self, settings: &LiveSettings) -> Option<String> {
+        let (start, end) = match self.get(settings) {
+            Float(v) if !v.is_finite() => return Some("must be a finite number".to_string()),
+            Float(v) => (v as f64, None),
+            Int(v) => (v as f64, None),
+            FloatRange(v) if !v.start.is_finite() || !v.end.is_finite() => return Some("must be finite numbers".to_string()),
+            FloatRange(v) => (v.start as f64, Some(v.end as f64)),
+            IntRange(v) => (v.start as f64, Some(v.end as f64)),
+            Text(_) | Table(_) => (0.0, None),
+        };
+
+        let what = if end.is_some() { "start" } else { "value" };
+        match self.limit {
+            Limit::AtLeast(min) if start < min => return Some(format!("{} must be at least {}, found {}", what, min, start)),
+            Limit::Above(min) if start <= min => return Some(format!("{} must be greater than {}, found {}", what, min, start)),
+            _ => {},
+        }
+
+        match end {
+            Some(end) if self.sampled && start >= end => return Some(format!("start must be less than end, found [{}, {}]", start, end)),
+            Some(end) if start > end => return Some(format!("start must not be greater than end, found [{}, {}]", start, end)),
+            _ => {},
+        }
+
+        self.check.as_ref().and_then(|check| (check.check)(&self.get(settings)))
+    }
+
+    /// Valid values in words, e.g. `> 0` or `1 <= start < end`.
+    pub fn valid_values(&self) -> String {
+        if let Some(check) = &self.check {
+            return check.valid.to_string();
+        }
+
+        let is_range = matches!(self.default, FloatRange(_) | IntRange(_));
+        let order = match self.sampled {
+            true => "start < end",
+            false => "start <= end",
+        };
+
+        match (self.limit, is_range) {
+            (Limit::None, false) => "any".to_string(),
+            (Limit::AtLeast(min), false) => format!(">= {}", min),
+            (Limit::Above(min), false) => format!("> {}", min),
+            (Limit::None, true) => order.to_string(),
+            (Limit::AtLeast(min), true) => format!("{} <= {}", min, order),
+            (Limit::Above(min), true) => format!("{} < {}", min, order),
+        }
+    }
+
+    /// Everything about the setting, for `--explain`.
+    pub fn explain(&self) -> String {
+        let mut result = format!("{}\n    {}\n", self.name, self.description);
+        result += &format!("    type:    {}\n", self.default.type_name());
+        result += &format!("    default: {}\n", self.default);
+        if !self.unit.is_empty() {
+            result += &format!("    unit:    {}\n", self.unit);
+        }
+        result += &format!("    valid:   {}\n", self.valid_values());
+        if self.restart_only {
+            result += "    Fixed for the world lifetime, a change applies to a new world only.\n";
+        }
+        result
+    }
+}
+
+pub fn find_setting(name: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|s| s.name == name)
+}
+
+/// Problems of `settings` as (key, message).
+pub fn validate_settings(settings: &LiveSettings) -> Vec<(&'static str, String)> {
+    SETTINGS.iter()
+        .filter_map(|s| s.validate(settings).map(|message| (s.name, message)))
+        .collect()
+}
+
+/// The default settings file: every setting with its description, grouped by section.
+pub fn default_settings_file() -> String {
+    let mut result = String::from(
+        "# Bacteries simulation settings (TOML). Missing keys keep their defaults, unknown keys are errors.\n\
+         # `live-sim --explain <key>` describes a setting.\n\
+         # `extends = \"base.toml\"` reads another file first, the keys of this one win.\n\
+         # `extends = \"preset:<name>\"` starts from a built-in preset instead, e.g. \"preset:fast_evolution\".\n");
+    let mut section = "";
+    for setting in SETTINGS.iter() {
+        if setting.section != section {
+            section = setting.section;
+            result += &format!("\n# --- {} ---\n", section);
+        }
+
+        let mut notes = Vec::new();
+        if !setting.unit.is_empty() {
+            notes.push(setting.unit.to_string());
+        }
+        if setting.limit != Limit::None || setting.sampled || setting.check.is_some() {
+            notes.push(setting.valid_values());
+        }
+        if setting.restart_only {
+            notes.push("new world only".to_string());
+        }
+        let notes = match notes.is_empty() {
+            true => String::new(),
+            false => format!(" ({})", notes.join(", ")),
+        };
+        result += &format!("\n# {}{}\n{} = {}\n", setting.description, notes, setting.name, setting.default);
+    }
+    result
+}
+
+pub static SETTINGS: &[Setting] = &[
+    // Timing
+    Setting {
+        name: "time_step",
+        section: "Timing",
+        default: Float(1.0 / 60.0),
+        unit: "s",
+        limit: Limit::Above(0.0),
+        sampled: false,
+        restart_only: true,
+        check: None,
+        description: "Fixed simulation step, the same for physics and bacteries processing.",
+        get: |s| Float(s.time_step),
+        set: |s, v| s.time_step = v.float(),
+    },
+    Setting {
+        name: "speed",
+        section: "Timing",
+        default: Float(1.0),
+        unit: "x real time",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "World speed of real-time front-ends, 0 runs as fast as possible.",
+        get: |s| Float(s.speed),
+        set: |s, v| s.speed = v.float(),
+    },
+    Setting {
+        name: "max_gen_rate",
+        section: "Timing",
+        default: Float(60.0),
+        unit: "events/s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Events per simulated second of a `*_rate` gene equal to 1.0.",
+        get: |s| Float(s.max_gen_rate),
+        set: |s, v| s.max_gen_rate = v.float(),
+    },
+    Setting {
+        name: "seed",
+        section: "Timing",
+        default: Int(0),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: true,
+        check: None,
+        description: "Seed of the world random generator, 0 picks a random one and prints it.",
+        get: |s| Int(s.seed),
+        set: |s, v| s.seed = v.int(),
+    },
+
+    // Day and light
+    Setting {
+        name: "night_light_force",
+        section: "Day and light",
+        default: Float(0.2),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Photosynthesis light multiplier at night.",
+        get: |s| Float(s.light_force[0]),
+        set: |s, v| s.light_force[0] = v.float(),
+    },
+    Setting {
+        name: "morning_light_force",
+        section: "Day and light",
+        default: Float(0.65),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Photosynthesis light multiplier in the morning.",
+        get: |s| Float(s.light_force[1]),
+        set: |s, v| s.light_force[1] = v.float(),
+    },
+    Setting {
+        name: "day_light_force",
+        section: "Day and light",
+        default: Float(1.0),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Photosynthesis light multiplier at midday.",
+        get: |s| Float(s.light_force[2]),
+        set: |s, v| s.light_force[2] = v.float(),
+    },
+    Setting {
+        name: "day_length_sec",
+        section: "Day and light",
+        default: Float(480.0),
+        unit: "s",
+        limit: Limit::Above(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Length of one day and night cycle.",
+        get: |s| Float(s.day_length_sec),
+        set: |s, v| s.day_length_sec = v.float(),
+    },
+    Setting {
+        name: "light_cell_size",
+        section: "Day and light",
+        default: Float(40.0),
+        unit: "px",
+        limit: Limit::AtLeast(1.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Side of a light cell, photosynthesis uses the light of the cell under the bacterium center.",
+        get: |s| Float(s.light_cell_size),
+        set: |s, v| s.light_cell_size = v.float(),
+    },
+    Setting {
+        name: "light_gradient_x",
+        section: "Day and light",
+        default: Float(0.0),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Light change from the middle to the right edge, as a share: 0.5 gives 0.5 on the left edge and 1.5 on the right one.",
+        get: |s| Float(s.light_gradient_x),
+        set: |s, v| s.light_gradient_x = v.float(),
+    },
+    Setting {
+        name: "light_gradient_y",
+        section: "Day and light",
+        default: Float(0.0),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Light change from the middle to the bottom edge, as a share: 0.5 gives 0.5 on the top edge and 1.5 on the bottom one.",
+        get: |s| Float(s.light_gradient_y),
+        set: |s, v| s.light_gradient_y = v.float(),
+    },
+    Setting {
+        name: "light_spot",
+        section: "Day and light",
+        default: Text(Cow::Borrowed("none")),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: LIGHT_SPOTS_VALID, check: check_name::<LightSpot> }),
+        description: "none: no spotlight, fixed: spotlight at `light_spot_x`, `light_spot_y`, sun: spotlight at `light_spot_y` crossing the world from left to right once a day.",
+        get: |s| Text(Cow::Borrowed(s.light_spot.name())),
+        set: |s, v| s.light_spot = v.named(),
+    },
+    Setting {
+        name: "light_spot_x",
+        section: "Day and light",
+        default: Float(0.5),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Spotlight center as a share of the world width.",
+        get: |s| Float(s.light_spot_x),
+        set: |s, v| s.light_spot_x = v.float(),
+    },
+    Setting {
+        name: "light_spot_y",
+        section: "Day and light",
+        default: Float(0.5),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Spotlight center as a share of the world height.",
+        get: |s| Float(s.light_spot_y),
+        set: |s, v| s.light_spot_y = v.float(),
+    },
+    Setting {
+        name: "light_spot_radius",
+        section: "Day and light",
+        default: Float(400.0),
+        unit: "px",
+        limit: Limit::Above(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Distance from the spotlight center at which the light falls to 1/e of the center.",
+        get: |s| Float(s.light_spot_radius),
+        set: |s, v| s.light_spot_radius = v.float(),
+    },
+    Setting {
+        name: "light_shading",
+        section: "Day and light",
+        default: Float(0.3),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: SHADING_VALID, check: check_shading }),
+        description: "Share of the light each bacterium takes from the cells under its body, the bacteries below get the rest.",
+        get: |s| Float(s.light_shading),
+        set: |s, v| s.light_shading = v.float(),
+    },
+
+    // Bodies
+    Setting {
+        name: "capacity",
+        section: "Bodies",
+        default: Int(1000),
+        unit: "bacteries",
+        limit: Limit::AtLeast(1.0),
+        sampled: false,
+        restart_only: true,
+        check: None,
+        description: "Bacteria slots in the world, dead ones are reused for newborns.",
+        get: |s| Int(s.capacity),
+        set: |s, v| s.capacity = v.int(),
+    },
+    Setting {
+        name: "radius_range",
+        section: "Bodies",
+        default: IntRange(8..20),
+        unit: "px",
+        limit: Limit::AtLeast(1.0),
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Radius of spawned bacteries, mutated radii are clamped to it.",
+        get: |s| IntRange(s.radius_range.clone()),
+        set: |s, v| s.radius_range = v.int_range(),
+    },
+    Setting {
+        name: "move_force",
+        section: "Bodies",
+        default: Float(100.0),
+        unit: "force",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Movement push of a `movement_force` gene equal to 1.0.",
+        get: |s| Float(s.move_force),
+        set: |s, v| s.move_force = v.float(),
+    },
+    Setting {
+        name: "energy_for_move",
+        section: "Bodies",
+        default: Float(0.5),
+        unit: "energy",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Energy one movement costs at `movement_force` 1.0.",
+        get: |s| Float(s.energy_for_move),
+        set: |s, v| s.energy_for_move = v.float(),
+    },
+    Setting {
+        name: "vel_range",
+        section: "Bodies",
+        default: FloatRange(-1.0..1.0),
+        unit: "",
+        limit: Limit::None,
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Random direction components of a movement, scaled by the movement force.",
+        get: |s| FloatRange(s.vel_range.clone()),
+        set: |s, v| s.vel_range = v.float_range(),
+    },
+    Setting {
+        name: "max_repulsive_force",
+        section: "Bodies",
+        default: Float(300.0),
+        unit: "force",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Push on a touching bacterium of a `repulsive_force` gene equal to 1.0.",
+        get: |s| Float(s.max_repulsive_force),
+        set: |s, v| s.max_repulsive_force = v.float(),
+    },
+    Setting {
+        name: "flagella_num_range",
+        section: "Bodies",
+        default: IntRange(6..14),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Drawn flagella count, picked by `movement_rate`.",
+        get: |s| IntRange(s.flagella_num_range.clone()),
+        set: |s, v| s.flagella_num_range = v.int_range(),
+    },
+    Setting {
+        name: "flagella_len_range",
+        section: "Bodies",
+        default: IntRange(2..8),
+        unit: "px",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Drawn flagella length, picked by `movement_force`.",
+        get: |s| IntRange(s.flagella_len_range.clone()),
+        set: |s, v| s.flagella_len_range = v.int_range(),
+    },
+
+    // Life and energy
+    Setting {
+        name: "max_alive",
+        section: "Life and energy",
+        default: Float(100.0),
+        unit: "s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Lifetime a bacterium can regenerate itself up to.",
+        get: |s| Float(s.max_alive),
+        set: |s, v| s.max_alive = v.float(),
+    },
+    Setting {
+        name: "start_alive_range",
+        section: "Life and energy",
+        default: FloatRange(1.0..100.0),
+        unit: "s",
+        limit: Limit::None,
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Lifetime of new bacteries.",
+        get: |s| FloatRange(s.start_alive_range.clone()),
+        set: |s, v| s.start_alive_range = v.float_range(),
+    },
+    Setting {
+        name: "dead_time",
+        section: "Life and energy",
+        default: Float(0.0),
+        unit: "s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "A bacterium with this much lifetime left or less is dead.",
+        get: |s| Float(s.dead_time),
+        set: |s, v| s.dead_time = v.float(),
+    },
+    Setting {
+        name: "start_energy",
+        section: "Life and energy",
+        default: Float(1.0),
+        unit: "energy",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Energy of spawned bacteries and of children of division. A mated child gets what its parents paid.",
+        get: |s| Float(s.start_energy),
+        set: |s, v| s.start_energy = v.float(),
+    },
+    Setting {
+        name: "division_energy",
+        section: "Life and energy",
+        default: Float(10.0),
+        unit: "energy",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Energy a division needs and costs.",
+        get: |s| Float(s.division_energy),
+        set: |s, v| s.division_energy = v.float(),
+    },
+    Setting {
+        name: "alive_to_energy_coef",
+        section: "Life and energy",
+        default: Float(0.1),
+        unit: "s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Lifetime one regeneration adds for 1 energy.",
+        get: |s| Float(s.alive_to_energy_coef),
+        set: |s, v| s.alive_to_energy_coef = v.float(),
+    },
+    Setting {
+        name: "max_energy_distribution",
+        section: "Life and energy",
+        default: Float(10.0),
+        unit: "energy/s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Energy passed to a touching bacterium at `energy_distribution` 1.0.",
+        get: |s| Float(s.max_energy_distribution),
+        set: |s, v| s.max_energy_distribution = v.float(),
+    },
+
+    // Feeding
+    Setting {
+        name: "photosynth_rate",
+        section: "Feeding",
+        default: Float(0.02),
+        unit: "energy/s per px^2",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Photosynthesis per body area at light 1.0 and `photosynth` 1.0.",
+        get: |s| Float(s.photosynth_rate),
+        set: |s, v| s.photosynth_rate = v.float(),
+    },
+    Setting {
+        name: "carnivore_rate",
+        section: "Feeding",
+        default: Float(10.0),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Carnivore gain is `carnivore_rate^2 - carnivore_cost` energy per second of contact at `carnivore` 1.0.",
+        get: |s| Float(s.carnivore_rate),
+        set: |s, v| s.carnivore_rate = v.float(),
+    },
+    Setting {
+        name: "carnivore_damage",
+        section: "Feeding",
+        default: Float(15.0),
+        unit: "s/s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Lifetime per second of contact a stronger carnivore takes, before defence.",
+        get: |s| Float(s.carnivore_damage),
+        set: |s, v| s.carnivore_damage = v.float(),
+    },
+    Setting {
+        name: "defence",
+        section: "Feeding",
+        default: Float(15.0),
+        unit: "s/s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Damage blocked by a `defence` gene equal to 1.0.",
+        get: |s| Float(s.defence),
+        set: |s, v| s.defence = v.float(),
+    },
+    Setting {
+        name: "carnivore_cost",
+        section: "Feeding",
+        default: Float(20.0),
+        unit: "energy/s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Upkeep subtracted from the carnivore gain.",
+        get: |s| Float(s.carnivore_cost),
+        set: |s, v| s.carnivore_cost = v.float(),
+    },
+    Setting {
+        name: "on_dead_matter",
+        section: "Feeding",
+        default: Float(0.5),
+        unit: "matter",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Organic matter one death leaves.",
+        get: |s| Float(s.on_dead_matter),
+        set: |s, v| s.on_dead_matter = v.float(),
+    },
+    Setting {
+        name: "saprophyte_rate",
+        section: "Feeding",
+        default: Float(1.0),
+        unit: "energy/s",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Energy a `saprophyte` 1.0 gains while it eats its full `saprophyte_consumption`.",
+        get: |s| Float(s.saprophyte_rate),
+        set: |s, v| s.saprophyte_rate = v.float(),
+    },
+    Setting {
+        name: "saprophyte_consumption",
+        section: "Feeding",
+        default: Float(60.0),
+        unit: "matter/s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Organic matter a saprophyte eats from the cells it overlaps, the gain shrinks when there is less.",
+        get: |s| Float(s.saprophyte_consumption),
+        set: |s, v| s.saprophyte_consumption = v.float(),
+    },
+    Setting {
+        name: "organic_cell_size",
+        section: "Feeding",
+        default: Float(40.0),
+        unit: "px",
+        limit: Limit::AtLeast(1.0),
+        sampled: false,
+        restart_only: true,
+        check: None,
+        description: "Side of an organic matter cell. Deaths leave matter in the cells of the body.",
+        get: |s| Float(s.organic_cell_size),
+        set: |s, v| s.organic_cell_size = v.float(),
+    },
+    Setting {
+        name: "organic_diffusion",
+        section: "Feeding",
+        default: Float(0.05),
+        unit: "1/s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Share of organic matter a cell passes to each neighbour cell per second.",
+        get: |s| Float(s.organic_diffusion),
+        set: |s, v| s.organic_diffusion = v.float(),
+    },
+    Setting {
+        name: "organic_decay",
+        section: "Feeding",
+        default: Float(0.05),
+        unit: "1/s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Share of organic matter that decays per second.",
+        get: |s| Float(s.organic_decay),
+        set: |s, v| s.organic_decay = v.float(),
+    },
+
+    // Mutation
+    Setting {
+        name: "genome_mut_range",
+        section: "Mutation",
+        default: FloatRange(0.9..1.1),
+        unit: "x",
+        limit: Limit::None,
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Factor gens are multiplied by on division in the multiplicative and jump models.",
+        get: |s| FloatRange(s.genome_mut_range.clone()),
+        set: |s, v| s.genome_mut_range = v.float_range(),
+    },
+    Setting {
+        name: "radius_mut_range",
+        section: "Mutation",
+        default: FloatRange(0.9..1.1),
+        unit: "x",
+        limit: Limit::None,
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Factor the radius is multiplied by on division.",
+        get: |s| FloatRange(s.radius_mut_range.clone()),
+        set: |s, v| s.radius_mut_range = v.float_range(),
+    },
+    Setting {
+        name: "mutation_model",
+        section: "Mutation",
+        default: Text(Cow::Borrowed("multiplicative")),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: MUTATION_MODELS_VALID, check: check_name::<MutationModel> }),
+        description: "How gens change on division: multiplicative by `genome_mut_range`, gaussian noise, point replacement or multiplicative with jumps.",
+        get: |s| Text(Cow::Borrowed(s.mutation_model.name())),
+        set: |s, v| s.mutation_model = v.named(),
+    },
+    Setting {
+        name: "gene_mutation_models",
+        section: "Mutation",
+        default: Table(BTreeMap::new()),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: "{ gen = model }, e.g. { carnivore = \"gaussian\" }", check: check_gene_mutation_models }),
+        description: "Mutation model of single gens instead of `mutation_model`.",
+        get: |s| Table(s.gene_mutation_models.iter().map(|(&id, m)| (GENES[id].name.to_string(), m.name().to_string())).collect()),
+        set: |s, v| s.gene_mutation_models = v.table().iter()
+            .map(|(gen, model)| (find_gen(gen).unwrap(), MutationModel::from_name(model).unwrap()))
+            .collect(),
+    },
+    Setting {
+        name: "mutation_sigma",
+        section: "Mutation",
+        default: Float(0.02),
+        unit: "",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Standard deviation of the gaussian model, in gen units (gens of a bacterium sum to 1).",
+        get: |s| Float(s.mutation_sigma),
+        set: |s, v| s.mutation_sigma = v.float(),
+    },
+    Setting {
+        name: "mutation_point_chance",
+        section: "Mutation",
+        default: Float(0.1),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: PROBABILITY_VALID, check: check_probability }),
+        description: "Chance of a gen to get a new random value in the point model.",
+        get: |s| Float(s.mutation_point_chance),
+        set: |s, v| s.mutation_point_chance = v.float(),
+    },
+    Setting {
+        name: "mutation_jump_chance",
+        section: "Mutation",
+        default: Float(0.01),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: PROBABILITY_VALID, check: check_probability }),
+        description: "Chance of a gen to jump in the jump model.",
+        get: |s| Float(s.mutation_jump_chance),
+        set: |s, v| s.mutation_jump_chance = v.float(),
+    },
+    Setting {
+        name: "mutation_jump_range",
+        section: "Mutation",
+        default: FloatRange(0.2..5.0),
+        unit: "x",
+        limit: Limit::AtLeast(0.0),
+        sampled: true,
+        restart_only: false,
+        check: None,
+        description: "Extra factor of a jump.",
+        get: |s| FloatRange(s.mutation_jump_range.clone()),
+        set: |s, v| s.mutation_jump_range = v.float_range(),
+    },
+
+    // Reproduction
+    Setting {
+        name: "reproduction_mode",
+        section: "Reproduction",
+        default: Text(Cow::Borrowed("asexual")),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: REPRODUCTION_MODES_VALID, check: check_name::<ReproductionMode> }),
+        description: "asexual: division into mutated clones, sexual: mating of colliding compatible bacteries only, mixed: both.",
+        get: |s| Text(Cow::Borrowed(s.reproduction_mode.name())),
+        set: |s, v| s.reproduction_mode = v.named(),
+    },
+    Setting {
+        name: "crossover",
+        section: "Reproduction",
+        default: Text(Cow::Borrowed("uniform")),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: CROSSOVERS_VALID, check: check_name::<Crossover> }),
+        description: "How a mated child gets its gens, before the mutation: uniform takes each gen from one parent, blended a random mean of both.",
+        get: |s| Text(Cow::Borrowed(s.crossover.name())),
+        set: |s, v| s.crossover = v.named(),
+    },
+    Setting {
+        name: "mating_chance",
+        section: "Reproduction",
+        default: Float(0.5),
+        unit: "",
+        limit: Limit::None,
+        sampled: false,
+        restart_only: false,
+        check: Some(Check { valid: PROBABILITY_VALID, check: check_probability }),
+        description: "Chance that a new contact of two compatible bacteries, each with half of `division_energy`, gives a child.",
+        get: |s| Float(s.mating_chance),
+        set: |s, v| s.mating_chance = v.float(),
+    },
+    Setting {
+        name: "mating_distance",
+        section: "Reproduction",
+        default: Float(1.0),
+        unit: "",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Compatible bacteries differ at most by it: the sum of the gen differences of the genotype, 0 is equal, 2 is nothing in common.",
+        get: |s| Float(s.mating_distance),
+        set: |s, v| s.mating_distance = v.float(),
+    },
+
+    // Species
+    Setting {
+        name: "species_interval_sec",
+        section: "Species",
+        default: Float(10.0),
+        unit: "s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Simulated seconds between the species updates, 0 disables them.",
+        get: |s| Float(s.species_interval_sec),
+        set: |s, v| s.species_interval_sec = v.float(),
+    },
+    Setting {
+        name: "species_distance",
+        section: "Species",
+        default: Float(0.4),
+        unit: "",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "A bacterium belongs to the species with the closest representative within it (sum of the genotype gen differences), otherwise it starts a new species.",
+        get: |s| Float(s.species_distance),
+        set: |s, v| s.species_distance = v.float(),
+    },
+
+    // Autosave
+    Setting {
+        name: "autosave_interval_sec",
+        section: "Autosave",
+        default: Float(600.0),
+        unit: "s",
+        limit: Limit::AtLeast(0.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Simulated seconds between autosave snapshots, 0 disables autosave.",
+        get: |s| Float(s.autosave_interval_sec),
+        set: |s, v| s.autosave_interval_sec = v.float(),
+    },
+    Setting {
+        name: "autosave_keep",
+        section: "Autosave",
+        default: Int(5),
+        unit: "files",
+        limit: Limit::AtLeast(1.0),
+        sampled: false,
+        restart_only: false,
+        check: None,
+        description: "Number of the newest autosave snapshots kept, older ones are deleted.",
+        get: |s| Int(s.autosave_keep),
+        set: |s, v| s.autosave_keep = v.int(),
+    },
+];
+
+const MUTATION_MODELS_VALID: &str = "multiplicative, gaussian, point or jump";
+const PROBABILITY_VALID: &str = "0 <= value <= 1";
+const REPRODUCTION_MODES_VALID: &str = "asexual, sexual or mixed";
+const CROSSOVERS_VALID: &str = "uniform or blended";
+const LIGHT_SPOTS_VALID: &str = "none, fixed or sun";
+const SHADING_VALID: &str = "0 <= value < 1";
+
+fn check_probability(value: &SettingValue) -> Option<String> {
+    match value {
+        Float(v) if !(0.0..=1.0).contains(v) => Some(format!("must be between 0 and 1, found {}", v)),
+        _ => None,
+    }
+}
+
+fn check_name<T: NamedEnum>(value: &SettingValue) -> Option<String> {
+    match value {
+        Text(v) if T::from_name(v).is_none() => Some(format!("must be {}, found {}", T::names(), v)),
+        _ => None,
+    }
+}
+
+fn check_shading(value: &SettingValue) -> Option<String> {
+    match value {
+        Float(v) if !(0.0..1.0).contains(v) => Some(format!("must be at least 0 and below 1, found {}", v)),
+        _ => None,
+    }
+}
+
+fn check_gene_mutation_models(value: &SettingValue) -> Option<String> {
+    let Table(models) = value else { return None };
+    for (gen, model) in models {
+        if find_gen(gen).is_none() {
+            return Some(format!("{} is not a gen", gen));
+        }
+        if MutationModel::from_name(model).is_none() {
+            return Some(format!("{}: must be {}, found {}", gen, MutationModel::names(), model));
+        }
+    }
+    None
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use rapier2d::na::Vector2;
+use rapier2d::prelude::PhysicsPipeline;
+
+use super::{LiveData, LiveSettings};
+use super::app::AppData;
+use super::grid::Grid;
+use super::bacteries_processing::process_bacteries;
+use super::physics::physics_step;
+use super::settings_schema::{SettingValue, SETTINGS};
+use super::utils::{LiveRng, interpolate_floats, rand_range_vec2, rand_ranged_i32};
+
+/// Real-time catch-up limit, the rest of a too long frame is dropped instead of freezing the front-end.
+const MAX_STEPS_PER_ADVANCE: u32 = 100;
+/// Longest wait `next_step_in` asks for, so a very slow world still reacts to settings and shutdown.
+const MAX_SLEEP: Duration = Duration::from_millis(50);
+
+/// One world: its state, timing and physics pipeline.
+/// Owned by the front-end, any number of them can live in one process.
+pub struct Simulation {
+    app: AppData,
+    physics_pipeline: PhysicsPipeline,
+    /// Simulated seconds owed to real time, see `advance`.
+    accumulator: f32,
+    speed: f32,
+}
+
+impl Simulation {
+    /// Create the start world: physics, bacteries and edges.
+    /// `width` and `height` is the world size in pixels, usually the screen size.
+    /// A zero `settings.seed` is replaced with a random one, so the run can be repeated later.
+    pub fn new(width: usize, height: usize, mut settings: LiveSettings) -> Simulation {
+        if settings.seed == 0 {
+            settings.seed = rand::random::<u64>().max(1);
+        }
+        eprintln!("Simulation seed: {}", settings.seed);
+
+        let mut app = AppData::new(width, height, settings.time_step);
+        app.live_data.rng = LiveRng::new(settings.seed);
+        app.live_data.organic = Grid::new(width, height, settings.organic_cell_size);
+        app.live_data.settings = settings;
+        app.frames_in_day = app.live_data.settings.day_length_sec / app.delta_time;
+        app.build_physics();
+        let radius = app.live_data.settings.radius_range.clone();
+        app.spawn_bacteries(radius);
+        app.with_edges(100.0, 100.0);
+
+        for i in app.live_data.bacteries.into_iter() {
+            let x = rand_ranged_i32(&mut app.live_data.rng, -100..100) as f32;
+            let y = rand_ranged_i32(&mut app.live_data.rng, -100..100) as f32;
+            let rb = app.live_data.bacteries.rigidbody[i];
+            app.live_data.physics_data.get_rb_mut(rb).set_linvel(Vector2::new(x, y), true);
+        }
+
+        Simulation::from_app(app)
+    }
+
+    /// Wrap an already built world, e.g. one restored from a snapshot.
+    pub(crate) fn from_app(app: AppData) -> Simulation {
+        Simulation {
+            speed: app.live_data.settings.speed,
+            app,
+            physics_pipeline: PhysicsPipeline::new(),
+            accumulator: 0.0,
+        }
+    }
+
+    /// Run as many fixed steps as `real_elapsed` wall time covers at the current speed.
+    /// Slow frames catch up with several steps, at most `MAX_STEPS_PER_ADVANCE`.
+    /// With unbounded speed (0.0) does exactly one step. Returns the number of steps done.
+    pub fn advance(&mut self, real_elapsed: Duration) -> u32 {
+        if self.is_unbounded() {
+            self.step();
+            return 1;
+        }
+
+        let dt = self.app.delta_time;
+        self.accumulator += real_elapsed.as_secs_f32() * self.speed;
+        let mut steps = 0;
+        while self.accumulator >= dt && steps < MAX_STEPS_PER_ADVANCE {
+            self.accumulator -= dt;
+            self.step();
+            steps += 1;
+        }
+
+        if steps == MAX_STEPS_PER_ADVANCE {
+            self.accumulator = self.accumulator.min(dt);
+        }
+
+        steps
+    }
+
+    /// Wall time until the next step is due, zero with unbounded speed and at most `MAX_SLEEP`.
+    pub fn next_step_in(&self) -> Duration {
+        if self.is_unbounded() {
+            return Duration::ZERO;
+        }
+
+        let left = (self.app.delta_time - self.accumulator).max(0.0);
+        Duration::try_from_secs_f32(left / self.speed).unwrap_or(MAX_SLEEP).min(MAX_SLEEP)
+    }
+
+    /// One logic tick: day cycle, random spawn, physics, light, bacteries processing and organic matter.
+    pub fn step(&mut self) {
+        let app = &mut self.app;
+        // In whole ticks, a float tick count stops counting after 2^24 ticks.
+        let ticks_per_day = app.frames_in_day.round().max(1.0) as u128;
+        app.day_progress = (app.frame_num % ticks_per_day) as f32 / ticks_per_day as f32;
+        app.live_data.light_force = interpolate_floats(&app.live_data.settings.light_force, app.day_progress);
+
+        if app.frame_num.is_multiple_of(100) {
+            let pos = rand_range_vec2(&mut app.live_data.rng, 0.0..app.width as f32, 0.0..app.height as f32);
+            let radius = app.live_data.settings.radius_range.clone();
+            let radius = rand_ranged_i32(&mut app.live_data.rng, radius);
+            app.live_data.spawn_bac(pos, radius, app.frame_num);
+        }
+
+        physics_step(&mut self.physics_pipeline, &mut app.live_data.physics_data);
+        app.live_data.sync_positions();
+        app.live_data.update_light(app.width, app.height, app.day_progress);
+        process_bacteries(app);
+        app.live_data.update_organic(app.delta_time);
+        app.live_data.prune_lineage();
+        app.live_data.update_species(app.frame_num);
+        app.frame_num += 1;
+    }
+
+    /// Replace the settings of the running world.
+    /// Settings fixed for the world lifetime keep their current values, their names are returned.
+    pub fn apply_settings(&mut self, mut settings: LiveSettings) -> Vec<&'static str> {
+        let current = &self.app.live_data.settings;
+        let mut kept = Vec::new();
+        for setting in SETTINGS.iter().filter(|s| s.restart_only) {
+            let value = setting.get(current);
+            let new_value = setting.get(&settings);
+            if new_value == value {
+                continue;
+            }
+
+            // 0 asks for a random seed, the world already has one.
+            if !(setting.name == "seed" && new_value == SettingValue::Int(0)) {
+                kept.push(setting.name);
+            }
+            setting.set(&mut settings, value).unwrap();
+        }
+
+        if settings.speed != current.speed {
+            self.speed = settings.speed;
+        }
+        self.app.frames_in_day = settings.day_length_sec / self.app.delta_time;
+        self.app.live_data.settings = settings;
+        kept
+    }
+
+    #[inline(always)]
+    pub fn app(&self) -> &AppData {
+        &self.app
+    }
+
+    #[inline(always)]
+    pub fn live_data(&self) -> &LiveData {
+        &self.app.live_data
+    }
+
+    #[inline(always)]
+    pub fn settings(&self) -> &LiveSettings {
+        &self.app.live_data.settings
+    }
+
+    #[inline(always)]
+    pub fn frame_num(&self) -> u128 {
+        self.app.frame_num
+    }
+
+    #[inline(always)]
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// 1.0 is real time, 2.0 twice faster, 0.0 (or less) runs as fast as possible.
+    #[inline(always)]
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    #[inline(always)]
+    pub fn is_unbounded(&self) -> bool {
+        self.speed <= 0.0
+    }
+
+    /// Simulated time since start, in seconds.
+    #[inline(always)]
+    pub fn time_sec(&self) -> f64 {
+        self.app.frame_num as f64 * self.app.delta_time as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::snapshot::write_snapshot;
+
+    fn state_after(seed: u64, ticks: u32) -> Vec<u8> {
+        let mut settings = LiveSettings::new();
+        settings.seed = seed;
+        settings.capacity = 300;
+        let mut simulation = Simulation::new(400, 300, settings);
+        for _ in 0..ticks {
+            simulation.step();
+        }
+        let mut result = Vec::new();
+        write_snapshot(&simulation, &mut result).unwrap();
+        result
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_run() {
+        assert_eq!(state_after(11, 100), state_after(11, 100));
+        assert_ne!(state_after(11, 100), state_after(12, 100));
+    }
+
+    #[test]
+    fn day_progress_keeps_counting_in_old_worlds() {
+        let mut settings = LiveSettings::new();
+        settings.seed = 1;
+        settings.capacity = 10;
+        let mut simulation = Simulation::new(200, 200, settings);
+        let ticks_per_day = simulation.app.frames_in_day.round() as u128;
+        // Far over 2^24 ticks, a quarter into the day.
+        simulation.app.frame_num = ticks_per_day * 100_000 + ticks_per_day / 4;
+        simulation.step();
+        assert_eq!(simulation.app.day_progress, 0.25);
+        simulation.step();
+        assert!(simulation.app.day_progress > 0.25);
+    }
+
+    fn small_simulation(speed: f32) -> Simulation {
+        let mut settings = LiveSettings::new();
+        settings.seed = 1;
+        settings.capacity = 10;
+        settings.speed = speed;
+        Simulation::new(200, 200, settings)
+    }
+
+    #[test]
+    fn advance_follows_real_time() {
+        let mut simulation = small_simulation(1.0);
+        let dt = simulation.app.delta_time;
+        assert_eq!(simulation.advance(Duration::from_secs_f32(dt * 0.5)), 0);
+        assert_eq!(simulation.advance(Duration::from_secs_f32(dt * 0.6)), 1);
+        assert_eq!(simulation.advance(Duration::from_secs_f32(dt * 3.0)), 3);
+        assert_eq!(simulation.frame_num(), 4);
+
+        simulation.set_speed(2.0);
+        assert_eq!(simulation.advance(Duration::from_secs_f32(dt * 2.0)), 4);
+
+        // A frozen front-end catches up only partly.
+        assert_eq!(simulation.advance(Duration::from_secs(3600)), MAX_STEPS_PER_ADVANCE);
+        assert!(simulation.next_step_in() <= Duration::from_secs_f32(dt));
+
+        simulation.set_speed(0.0);
+        assert_eq!(simulation.advance(Duration::ZERO), 1);
+        assert_eq!(simulation.next_step_in(), Duration::ZERO);
+    }
+
+    #[test]
+    fn next_step_in_is_bounded() {
+        let mut simulation = small_simulation(1.0);
+        let dt = simulation.app.delta_time;
+        let expected = Duration::from_secs_f32(dt).min(MAX_SLEEP);
+        assert_eq!(simulation.next_step_in(), expected);
+
+        simulation.set_speed(0.001);
+        assert_eq!(simulation.next_step_in(), MAX_SLEEP);
+        simulation.set_speed(1e-30);
+        assert_eq!(simulation.next_step_in(), MAX_SLEEP);
+        simulation.set_speed(f32::MIN_POSITIVE);
+        assert_eq!(simulation.next_step_in(), MAX_SLEEP);
+    }
+}
@@ -0,0 +1,563 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use micromath::vector::F32x2;
+use rapier2d::prelude::*;
+
+use super::app::AppData;
+use super::bacteries::Bacteries;
+use super::genome::{find_gen, GenId, Genome, GENES};
+use super::lineage::LineageRecord;
+use super::physics::PhysicsData;
+use super::species::Species;
+use super::save_load::{read_settings, write_settings};
+use super::simulation::Simulation;
+use super::utils::{LiveRng, LiveRngState};
+
+/// Snapshot file in the current directory the wallpaper resumes from.
+pub const DEFAULT_SNAPSHOT_FILE: &str = "live_snapshot.bin";
+
+const MAGIC: &[u8; 8] = b"LIVESNAP";
+/// Bump on any layout change, old snapshots are rejected instead of misread.
+pub const SNAPSHOT_VERSION: u32 = 10;
+
+/// Write the complete world to `path`. Goes through a temporary file, so a crash never leaves a broken snapshot.
+pub fn save_snapshot(simulation: &Simulation, path: &Path) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut out = BufWriter::new(File::create(&tmp_path)?);
+    write_snapshot(simulation, &mut out)?;
+    out.into_inner()?.sync_all()?;
+    fs::rename(tmp_path, path)
+}
+
+pub fn load_snapshot(path: &Path) -> io::Result<Simulation> {
+    read_snapshot(BufReader::new(File::open(path)?))
+}
+
+/// Directory in the current directory the wallpaper autosaves into.
+pub const DEFAULT_AUTOSAVE_DIR: &str = "snapshots";
+
+/// Periodic snapshots `snapshot_<sequence>_<tick>.bin` in one directory, only the last `autosave_keep` written stay.
+/// The sequence number goes on over the files already there, so a new world in the same directory
+/// rotates out the snapshots of the old one, whatever their ticks. Interval and count come from the simulation settings.
+pub struct Autosave {
+    dir: PathBuf,
+    next_tick: Option<u128>,
+    next_sequence: u64,
+}
+
+impl Autosave {
+    /// Creates `dir` if it is missing.
+    pub fn new(dir: &Path) -> io::Result<Autosave> {
+        fs::create_dir_all(dir)?;
+        let next_sequence = list_autosaves(dir)?.last().map_or(0, |(sequence, _)| sequence + 1);
+        Ok(Autosave {
+            dir: dir.to_path_buf(),
+            next_tick: None,
+            next_sequence,
+        })
+    }
+
+    /// Save if the interval has passed since the previous save (or since the first call).
+    /// Returns the path of the written snapshot.
+    pub fn tick(&mut self, simulation: &Simulation) -> io::Result<Option<PathBuf>> {
+        let Some(interval) = interval_ticks(simulation) else {
+            return Ok(None);
+        };
+
+        let frame_num = simulation.frame_num();
+        match self.next_tick {
+            Some(next_tick) if frame_num >= next_tick => {},
+            Some(_) => return Ok(None),
+            None => {
+                self.next_tick = Some(frame_num + interval);
+                return Ok(None);
+            },
+        }
+
+        self.save(simulation).map(Some)
+    }
+
+    /// Save right now, e.g. on shutdown, and delete the snapshots over the limit.
+    pub fn save(&mut self, simulation: &Simulation) -> io::Result<PathBuf> {
+        let frame_num = simulation.frame_num();
+        let path = self.dir.join(format!("snapshot_{:08}_{:012}.bin", self.next_sequence, frame_num));
+        save_snapshot(simulation, &path)?;
+        self.next_sequence += 1;
+        if let Some(interval) = interval_ticks(simulation) {
+            self.next_tick = Some(frame_num + interval);
+        }
+
+        let keep = simulation.settings().autosave_keep.max(1) as usize;
+        let snapshots = list_autosaves(&self.dir)?;
+        for (_, old) in snapshots.iter().take(snapshots.len().saturating_sub(keep)) {
+            fs::remove_file(old)?;
+        }
+
+        Ok(path)
+    }
+
+    /// The last written autosave snapshot in `dir`.
+    pub fn latest(dir: &Path) -> Option<PathBuf> {
+        list_autosaves(dir).ok()?.pop().map(|(_, path)| path)
+    }
+}
+
+/// Autosave snapshots in `dir` with their sequence numbers, in the order they were written.
+fn list_autosaves(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let mut result = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let sequence = name.strip_prefix("snapshot_")
+            .and_then(|rest| rest.strip_suffix(".bin"))
+            .and_then(|rest| rest.split_once('_'))
+            .and_then(|(sequence, _)| sequence.parse::<u64>().ok());
+        if let Some(sequence) = sequence {
+            result.push((sequence, path));
+        }
+    }
+
+    result.sort();
+    Ok(result)
+}
+
+/// `None` if autosave is disabled.
+fn interval_ticks(simulation: &Simulation) -> Option<u128> {
+    let settings = simulation.settings();
+    if settings.autosave_interval_sec <= 0.0 {
+        return None;
+    }
+
+    Some((settings.autosave_interval_sec / settings.time_step).round().max(1.0) as u128)
+}
+
+pub fn write_snapshot<W: Write>(simulation: &Simulation, out: W) -> io::Result<()> {
+    let mut w = SnapshotWriter { out };
+    let app = simulation.app();
+    let live = &app.live_data;
+    let bac = &live.bacteries;
+
+    w.bytes(MAGIC)?;
+    w.u32(SNAPSHOT_VERSION)?;
+
+    w.len(app.width)?;
+    w.len(app.height)?;
+    w.u128(app.frame_num)?;
+    w.f32(app.frames_in_day)?;
+    w.f32(app.day_progress)?;
+    w.f32(app.delta_time)?;
+
+    w.f32(live.light_force)?;
+    w.f32(live.organic.cell_size)?;
+    w.len(live.organic.cols)?;
+    w.len(live.organic.rows)?;
+    for value in live.organic.cells.iter() {
+        w.f32(*value)?;
+    }
+    w.u64(live.births)?;
+    w.u64(live.deaths)?;
+    w.u64(live.last_id)?;
+    w.len(live.lineage.prune_at)?;
+    w.len(live.lineage.records.len())?;
+    for (id, record) in live.lineage.records.iter() {
+        w.u64(*id)?;
+        w.u64(record.parent_id)?;
+        w.u128(record.birth_tick)?;
+        w.u32(record.generation)?;
+    }
+    w.len(live.contacts.len())?;
+    for (a, b) in live.contacts.iter() {
+        w.len(*a)?;
+        w.len(*b)?;
+    }
+    let rng = live.rng.state();
+    w.bytes(&rng.seed)?;
+    w.u64(rng.stream)?;
+    w.u128(rng.word_pos)?;
+    w.string(&write_settings(&live.settings))?;
+
+    w.len(bac.num)?;
+    for i in bac.into_iter() {
+        w.f32(bac.pos[i].x)?;
+        w.f32(bac.pos[i].y)?;
+        w.i32(bac.radius[i])?;
+        w.f32(bac.left_time[i])?;
+        w.f32(bac.energy[i])?;
+        w.len(bac.parent[i])?;
+        w.bool(bac.is_parented[i])?;
+        w.u64(bac.id[i])?;
+        w.u128(bac.birth_tick[i])?;
+        w.u64(bac.parent_id[i])?;
+        w.u64(bac.mate_id[i])?;
+        w.u32(bac.generation[i])?;
+        w.u64(bac.species[i])?;
+    }
+
+    // Named columns, so the gens of the registry can be reordered.
+    w.len(GENES.len())?;
+    for (gen, info) in bac.genome.iter().zip(GENES) {
+        w.string(info.name)?;
+        for i in bac.into_iter() {
+            w.f32(gen[i])?;
+        }
+    }
+
+    // Representatives in the order of the gen columns above.
+    let tracker = &live.species;
+    w.bool(tracker.next_tick.is_some())?;
+    w.u128(tracker.next_tick.unwrap_or_default())?;
+    w.len(tracker.species.len())?;
+    for species in tracker.species.iter() {
+        w.u64(species.id)?;
+        w.u128(species.born_tick)?;
+        w.bool(species.extinct_tick.is_some())?;
+        w.u128(species.extinct_tick.unwrap_or_default())?;
+        w.len(species.size)?;
+        w.len(species.peak_size)?;
+        for value in species.representative.iter() {
+            w.f32(*value)?;
+        }
+    }
+
+    // Slots into the physics sets, which are stored whole: solver and contact state included.
+    for i in bac.into_iter() {
+        let (index, generation) = bac.rigidbody[i].into_raw_parts();
+        w.u32(index)?;
+        w.u32(generation)?;
+        let (index, generation) = bac.collider[i].into_raw_parts();
+        w.u32(index)?;
+        w.u32(generation)?;
+    }
+    w.blob(&live.physics_data.to_bytes())?;
+
+    w.out.flush()
+}
+
+/// Rebuild the world from a snapshot, physics included.
+pub fn read_snapshot<R: Read>(input: R) -> io::Result<Simulation> {
+    let mut r = SnapshotReader { input };
+
+    if &r.array::<8>()? != MAGIC {
+        return Err(invalid("not a live snapshot"));
+    }
+    let version = r.u32()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(invalid(&format!("snapshot version {} is not supported, expected {}", version, SNAPSHOT_VERSION)));
+    }
+
+    let width = r.len()?;
+    let height = r.len()?;
+    let frame_num = r.u128()?;
+    let frames_in_day = r.f32()?;
+    let day_progress = r.f32()?;
+    let delta_time = r.f32()?;
+
+    let mut app = AppData::new(width, height, delta_time);
+    app.frame_num = frame_num;
+    app.frames_in_day = frames_in_day;
+    app.day_progress = day_progress;
+
+    let live = &mut app.live_data;
+    live.light_force = r.f32()?;
+    live.organic.cell_size = r.f32()?;
+    live.organic.cols = r.len()?;
+    live.organic.rows = r.len()?;
+    let cells = live.organic.cols.checked_mul(live.organic.rows).ok_or_else(|| invalid("broken organic grid in snapshot"))?;
+    for _ in 0..cells {
+        live.organic.cells.push(r.f32()?);
+    }
+    live.births = r.u64()?;
+    live.deaths = r.u64()?;
+    live.last_id = r.u64()?;
+    live.lineage.prune_at = r.len()?;
+    for _ in 0..r.len()? {
+        let id = r.u64()?;
+        live.lineage.record(id, LineageRecord { parent_id: r.u64()?, birth_tick: r.u128()?, generation: r.u32()? });
+    }
+    for _ in 0..r.len()? {
+        live.contacts.push((r.len()?, r.len()?));
+    }
+    live.rng = LiveRng::from_state(LiveRngState {
+        seed: r.array::<32>()?,
+        stream: r.u64()?,
+        word_pos: r.u128()?,
+    });
+    live.settings = match read_settings(&r.string()?) {
+        Ok(settings) => settings,
+        Err(errors) => return Err(invalid(&format!("bad snapshot settings: {}", errors[0]))),
+    };
+
+    let num = r.len()?;
+    if live.contacts.iter().any(|&(a, b)| a >= b || b >= num) {
+        return Err(invalid("broken contact in snapshot"));
+    }
+    let mut bac = Bacteries::empty();
+    bac.num = num;
+    for _ in 0..num {
+        bac.pos.push(F32x2 { x: r.f32()?, y: r.f32()? });
+        bac.radius.push(r.i32()?);
+        bac.left_time.push(r.f32()?);
+        bac.energy.push(r.f32()?);
+        bac.parent.push(r.len()?);
+        bac.is_parented.push(r.bool()?);
+        bac.id.push(r.u64()?);
+        bac.birth_tick.push(r.u128()?);
+        bac.parent_id.push(r.u64()?);
+        bac.mate_id.push(r.u64()?);
+        bac.generation.push(r.u32()?);
+        bac.species.push(r.u64()?);
+    }
+
+    let mut genome = Genome::empty();
+    genome.length = num;
+    let mut read_gens = vec![false; GENES.len()];
+    let mut columns = Vec::new();
+    for _ in 0..r.len()? {
+        let name = r.string()?;
+        let id = match find_gen(&name) {
+            Some(id) if !read_gens[id] => id,
+            _ => return Err(invalid(&format!("snapshot gen {} is unknown to this build", name))),
+        };
+        read_gens[id] = true;
+        columns.push(id);
+        for _ in 0..num {
+            genome[id].push(r.f32()?);
+        }
+    }
+    // Gens added after the snapshot was written start in the middle of their default range.
+    let missing = (0..GENES.len()).filter(|id| !read_gens[*id]).collect::<Vec<GenId>>();
+    let middle = |id: GenId| (GENES[id].default_range.start + GENES[id].default_range.end) / 2.0;
+    for id in missing.iter().copied() {
+        genome[id] = vec![middle(id); num];
+    }
+    if missing.iter().any(|id| GENES[*id].normalized) {
+        genome.normilize();
+    }
+    bac.genome = genome;
+    live.bacteries = bac;
+
+    let tracker = &mut live.species;
+    let has_next_tick = r.bool()?;
+    let next_tick = r.u128()?;
+    tracker.next_tick = has_next_tick.then_some(next_tick);
+    for _ in 0..r.len()? {
+        let mut species = Species {
+            id: r.u64()?,
+            born_tick: r.u128()?,
+            extinct_tick: None,
+            size: 0,
+            peak_size: 0,
+            representative: (0..GENES.len()).map(middle).collect(),
+        };
+        let extinct = r.bool()?;
+        let extinct_tick = r.u128()?;
+        species.extinct_tick = extinct.then_some(extinct_tick);
+        species.size = r.len()?;
+        species.peak_size = r.len()?;
+        for id in columns.iter().copied() {
+            species.representative[id] = r.f32()?;
+        }
+        tracker.species.push(species);
+    }
+
+    for _ in 0..num {
+        live.bacteries.rigidbody.push(RigidBodyHandle::from_raw_parts(r.u32()?, r.u32()?));
+        live.bacteries.collider.push(ColliderHandle::from_raw_parts(r.u32()?, r.u32()?));
+    }
+    live.physics_data = PhysicsData::from_bytes(&r.blob()?).map_err(|err| invalid(&format!("broken physics in snapshot: {}", err)))?;
+    let physics = &live.physics_data;
+    for i in 0..num {
+        if physics.bodies.get(live.bacteries.rigidbody[i]).is_none() || physics.colliders.get(live.bacteries.collider[i]).is_none() {
+            return Err(invalid("broken physics handle in snapshot"));
+        }
+    }
+
+    Ok(Simulation::from_app(app))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Little-endian primitives.
+struct SnapshotWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn bytes(&mut self, v: &[u8]) -> io::Result<()> {
+        self.out.write_all(v)
+    }
+
+    fn u32(&mut self, v: u32) -> io::Result<()> {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    fn u64(&mut self, v: u64) -> io::Result<()> {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    fn u128(&mut self, v: u128) -> io::Result<()> {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    fn i32(&mut self, v: i32) -> io::Result<()> {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    fn f32(&mut self, v: f32) -> io::Result<()> {
+        self.bytes(&v.to_le_bytes())
+    }
+
+    fn bool(&mut self, v: bool) -> io::Result<()> {
+        self.bytes(&[v as u8])
+    }
+
+    fn len(&mut self, v: usize) -> io::Result<()> {
+        self.u64(v as u64)
+    }
+
+    fn blob(&mut self, v: &[u8]) -> io::Result<()> {
+        self.len(v.len())?;
+        self.bytes(v)
+    }
+
+    fn string(&mut self, v: &str) -> io::Result<()> {
+        self.blob(v.as_bytes())
+    }
+}
+
+struct SnapshotReader<R: Read> {
+    input: R,
+}
+
+impl<R: Read> SnapshotReader<R> {
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut result = [0; N];
+        self.input.read_exact(&mut result)?;
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn u128(&mut self) -> io::Result<u128> {
+        Ok(u128::from_le_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        match self.array::<1>()? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(invalid("broken bool in snapshot")),
+        }
+    }
+
+    fn len(&mut self) -> io::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid("length in snapshot is too big"))
+    }
+
+    /// Length-prefixed bytes, see `SnapshotWriter::blob`.
+    fn blob(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.len()?;
+        let mut result = Vec::new();
+        self.input.by_ref().take(len as u64).read_to_end(&mut result)?;
+        if result.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(result)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.blob()?).map_err(|_| invalid("broken text in snapshot"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::LiveSettings;
+
+    fn small_world(seed: u64) -> Simulation {
+        let mut settings = LiveSettings::new();
+        settings.seed = seed;
+        settings.capacity = 300;
+        Simulation::new(400, 300, settings)
+    }
+
+    fn to_bytes(simulation: &Simulation) -> Vec<u8> {
+        let mut result = Vec::new();
+        write_snapshot(simulation, &mut result).unwrap();
+        result
+    }
+
+    fn run(simulation: &mut Simulation, ticks: u32) {
+        for _ in 0..ticks {
+            simulation.step();
+        }
+    }
+
+    #[test]
+    fn write_read_write_is_identical() {
+        let mut simulation = small_world(7);
+        run(&mut simulation, 200);
+        let bytes = to_bytes(&simulation);
+        assert_eq!(to_bytes(&read_snapshot(bytes.as_slice()).unwrap()), bytes);
+    }
+
+    #[test]
+    fn resumed_world_steps_the_same() {
+        let mut straight = small_world(42);
+        run(&mut straight, 200);
+        let mut resumed = read_snapshot(to_bytes(&straight).as_slice()).unwrap();
+        for _ in 0..4 {
+            run(&mut straight, 50);
+            run(&mut resumed, 50);
+            assert_eq!(to_bytes(&resumed), to_bytes(&straight));
+        }
+    }
+
+    #[test]
+    fn broken_snapshots_are_rejected() {
+        let mut bytes = to_bytes(&small_world(1));
+        assert!(read_snapshot(&bytes[..bytes.len() - 1]).is_err());
+        bytes[8] += 1;
+        assert!(read_snapshot(bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn autosave_rotates_in_write_order() {
+        let dir = std::env::temp_dir().join(format!("live_autosave_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // Snapshots of an older world that ran longer.
+        for name in ["snapshot_00000000_000000090000.bin", "snapshot_00000001_000000099000.bin"] {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let simulation = small_world(3);
+        let mut autosave = Autosave::new(&dir).unwrap();
+        let keep = simulation.settings().autosave_keep as usize;
+        let written = (0..keep).map(|_| autosave.save(&simulation).unwrap()).collect::<Vec<PathBuf>>();
+
+        let left = list_autosaves(&dir).unwrap().into_iter().map(|(_, path)| path).collect::<Vec<PathBuf>>();
+        assert_eq!(left, written);
+        assert_eq!(Autosave::latest(&dir).as_ref(), written.last());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
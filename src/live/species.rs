@@ -0,0 +1,172 @@
+use super::bacteries::Bacteries;
+use super::genome::{Gen, GENES};
+
+/// Group of bacteries with close genotypes, see `SpeciesTracker::update`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Species {
+    pub id: u64,
+    /// Gens of one member in `GENES` order, the member closest to the species mean at the last update.
+    pub representative: Vec<Gen>,
+    pub born_tick: u128,
+    /// `None` while the species has members.
+    pub extinct_tick: Option<u128>,
+    /// Members at the last update.
+    pub size: usize,
+    pub peak_size: usize,
+}
+
+impl Species {
+    pub fn is_extinct(&self) -> bool {
+        self.extinct_tick.is_some()
+    }
+}
+
+/// Every species seen since the start, extinct ones included, by ascending id.
+#[derive(Default, Clone, Debug)]
+pub struct SpeciesTracker {
+    pub species: Vec<Species>,
+    /// Tick of the next update, `None` before the first one.
+    pub next_tick: Option<u128>,
+}
+
+impl SpeciesTracker {
+    /// Assign `Bacteries::species` of every living bacterium: the living species with the closest representative
+    /// within `max_distance`, or a new species represented by the bacterium itself.
+    /// Species left without members die out, the others get a new representative.
+    pub fn update(&mut self, bac: &mut Bacteries, dead_time: f32, max_distance: f32, tick: u128) {
+        let mut living = self.species.iter().enumerate()
+            .filter(|(_, s)| !s.is_extinct())
+            .map(|(idx, _)| idx)
+            .collect::<Vec<usize>>();
+        let mut members = vec![Vec::new(); self.species.len()];
+
+        let alive = bac.into_iter().filter(|i| bac.is_alive(*i, dead_time)).collect::<Vec<usize>>();
+        for i in alive {
+            let closest = living.iter()
+                .map(|&idx| (idx, distance(bac, i, &self.species[idx].representative)))
+                .filter(|(_, d)| *d <= max_distance)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            let idx = match closest {
+                Some((idx, _)) => idx,
+                None => {
+                    let id = self.species.last().map_or(1, |s| s.id + 1);
+                    self.species.push(Species {
+                        id,
+                        representative: bac.genome.iter().map(|gen| gen[i]).collect(),
+                        born_tick: tick,
+                        extinct_tick: None,
+                        size: 0,
+                        peak_size: 0,
+                    });
+                    members.push(Vec::new());
+                    living.push(self.species.len() - 1);
+                    self.species.len() - 1
+                },
+            };
+            members[idx].push(i);
+            bac.species[i] = self.species[idx].id;
+        }
+
+        for idx in living {
+            let species = &mut self.species[idx];
+            let members = &members[idx];
+            species.size = members.len();
+            species.peak_size = species.peak_size.max(members.len());
+            if members.is_empty() {
+                species.extinct_tick = Some(tick);
+                continue;
+            }
+
+            let mean = (0..GENES.len())
+                .map(|id| members.iter().map(|&i| bac.genome[id][i]).sum::<Gen>() / members.len() as Gen)
+                .collect::<Vec<Gen>>();
+            let central = members.iter().copied()
+                .min_by(|&a, &b| distance(bac, a, &mean).total_cmp(&distance(bac, b, &mean)))
+                .unwrap();
+            species.representative = bac.genome.iter().map(|gen| gen[central]).collect();
+        }
+    }
+
+    pub fn living(&self) -> impl Iterator<Item = &Species> {
+        self.species.iter().filter(|s| !s.is_extinct())
+    }
+
+    pub fn extinct_count(&self) -> usize {
+        self.species.iter().filter(|s| s.is_extinct()).count()
+    }
+}
+
+/// `Genome::distance` between the bacterium `i` and `gens` in `GENES` order.
+fn distance(bac: &Bacteries, i: usize, gens: &[Gen]) -> f32 {
+    bac.genome.iter().zip(GENES).zip(gens)
+        .filter(|((_, info), _)| info.normalized)
+        .map(|((gen, _), value)| (gen[i] - value).abs())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::genome::{CARNIVORE, GenId, PHOTOSYNTH, SAPROPHYTE};
+    use crate::live::utils::LiveRng;
+
+    /// Living bacterium `i` with only the given normalized gens.
+    fn set_alive(bac: &mut Bacteries, i: usize, gens: &[(GenId, Gen)]) {
+        for (id, (gen, info)) in bac.genome.iter_mut().zip(GENES).enumerate() {
+            if info.normalized {
+                gen[i] = gens.iter().find(|(g, _)| *g == id).map_or(0.0, |(_, v)| *v);
+            }
+        }
+        bac.left_time[i] = 1.0;
+    }
+
+    fn gens_of(bac: &Bacteries, i: usize) -> Vec<Gen> {
+        bac.genome.iter().map(|gen| gen[i]).collect()
+    }
+
+    #[test]
+    fn species_are_born_represented_and_die_out() {
+        let mut bac = Bacteries::new(5, &mut LiveRng::new(1));
+        set_alive(&mut bac, 0, &[(PHOTOSYNTH, 1.0)]);
+        set_alive(&mut bac, 1, &[(PHOTOSYNTH, 0.9), (SAPROPHYTE, 0.1)]);
+        set_alive(&mut bac, 2, &[(PHOTOSYNTH, 0.8), (SAPROPHYTE, 0.2)]);
+        set_alive(&mut bac, 3, &[(CARNIVORE, 1.0)]);
+
+        let mut tracker = SpeciesTracker::default();
+        tracker.update(&mut bac, 0.0, 0.5, 10);
+        assert_eq!(bac.species, [1, 1, 1, 2, 0]);
+        assert_eq!(tracker.species.iter().map(|s| (s.id, s.size, s.born_tick)).collect::<Vec<_>>(), [(1, 3, 10), (2, 1, 10)]);
+        // The middle member is the closest to the mean.
+        assert_eq!(tracker.species[0].representative, gens_of(&bac, 1));
+
+        bac.left_time[3] = 0.0;
+        tracker.update(&mut bac, 0.0, 0.5, 20);
+        assert_eq!(tracker.species[1].extinct_tick, Some(20));
+        assert_eq!((tracker.species[1].size, tracker.species[1].peak_size), (0, 1));
+        assert_eq!((tracker.living().count(), tracker.extinct_count()), (1, 1));
+
+        // An extinct species does not come back, its returning genotype is a new species.
+        set_alive(&mut bac, 3, &[(CARNIVORE, 1.0)]);
+        set_alive(&mut bac, 4, &[(SAPROPHYTE, 1.0)]);
+        tracker.update(&mut bac, 0.0, 0.5, 30);
+        assert_eq!(bac.species, [1, 1, 1, 3, 4]);
+        assert_eq!(tracker.species[1].extinct_tick, Some(20));
+        assert_eq!(tracker.species[3].representative, gens_of(&bac, 4));
+        assert_eq!(tracker.living().map(|s| s.id).collect::<Vec<_>>(), [1, 3, 4]);
+    }
+
+    #[test]
+    fn a_bacterium_joins_the_closest_species() {
+        let mut bac = Bacteries::new(3, &mut LiveRng::new(1));
+        set_alive(&mut bac, 0, &[(PHOTOSYNTH, 1.0)]);
+        set_alive(&mut bac, 1, &[(SAPROPHYTE, 1.0)]);
+        let mut tracker = SpeciesTracker::default();
+        tracker.update(&mut bac, 0.0, 1.5, 0);
+        assert_eq!(bac.species[..2], [1, 2]);
+
+        set_alive(&mut bac, 2, &[(PHOTOSYNTH, 0.3), (SAPROPHYTE, 0.7)]);
+        tracker.update(&mut bac, 0.0, 1.5, 1);
+        assert_eq!(bac.species, [1, 2, 2]);
+    }
+}
@@ -1,22 +1,108 @@
 use std::ops::Range;
 
 use micromath::vector::F32x2;
-use rand::Rng;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// World-owned random generator. The same seed and settings give the same run on the same build.
+#[derive(Clone)]
+pub struct LiveRng(ChaCha8Rng);
+
+/// Everything needed to continue a `LiveRng` sequence, see `LiveRng::state`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LiveRngState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    pub word_pos: u128,
+}
+
+impl LiveRng {
+    pub fn new(seed: u64) -> LiveRng {
+        LiveRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    pub fn state(&self) -> LiveRngState {
+        LiveRngState {
+            seed: self.0.get_seed(),
+            stream: self.0.get_stream(),
+            word_pos: self.0.get_word_pos(),
+        }
+    }
+
+    pub fn from_state(state: LiveRngState) -> LiveRng {
+        let mut rng = ChaCha8Rng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(state.word_pos);
+        LiveRng(rng)
+    }
+}
+
+impl Default for LiveRng {
+    fn default() -> LiveRng {
+        LiveRng::new(0)
+    }
+}
+
+impl RngCore for LiveRng {
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    #[inline(always)]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    #[inline(always)]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
 
 #[inline(always)]
-pub fn rand_range_vec2(x: Range::<f32>, y: Range::<f32>) -> F32x2 {
+pub fn rand_range_vec2(rng: &mut LiveRng, x: Range::<f32>, y: Range::<f32>) -> F32x2 {
     F32x2{
-        x: rand::thread_rng().gen_range(x),
-        y: rand::thread_rng().gen_range(y),
+        x: rng.gen_range(x),
+        y: rng.gen_range(y),
     }
 }
 
 #[inline(always)]
-pub fn rand_ranged_i32(range: Range::<i32>) -> i32 {
-    rand::thread_rng().gen_range(range)
+pub fn rand_ranged_i32(rng: &mut LiveRng, range: Range::<i32>) -> i32 {
+    rng.gen_range(range)
 }
 
 #[inline(always)]
-pub fn rand_ranged_f32(range: Range::<f32>) -> f32 {
-    rand::thread_rng().gen_range(range)
+pub fn rand_ranged_f32(rng: &mut LiveRng, range: Range::<f32>) -> f32 {
+    rng.gen_range(range)
+}
+
+/// True with `probability` in 0..=1.
+#[inline(always)]
+pub fn rand_chance(rng: &mut LiveRng, probability: f32) -> bool {
+    probability > rng.gen_range(0.0..1.0)
+}
+
+/// Normal distribution with mean 0 (Box-Muller).
+#[inline(always)]
+pub fn rand_normal_f32(rng: &mut LiveRng, sigma: f32) -> f32 {
+    let u1 = 1.0 - rng.gen_range(0.0f32..1.0);
+    let u2 = rng.gen_range(0.0f32..1.0);
+    sigma * (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+/// Linear interpolation over evenly spaced `floats`, `weight` in 0..=1.
+pub fn interpolate_floats(floats: &[f32], weight: f32) -> f32 {
+    let segment = 1.0 / (floats.len() - 1) as f32;
+    let index1 = ((weight / segment).floor() as usize).min(floats.len() - 2);
+    let index2 = index1 + 1;
+    let segment_weight = (weight - index1 as f32 * segment) / segment;
+
+    (1.0 - segment_weight) * floats[index1] + segment_weight * floats[index2]
 }
\ No newline at end of file
@@ -1,18 +1,14 @@
 //#![windows_subsystem = "windows"]
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
 
-use live::utils::{
-    rand_range_vec2,
-    rand_ranged_i32,
-};
-
-use live::save_load::{load_settings, try_save};
-use rand::Rng;
+use live_wallpapers::live;
 
-use rapier2d::{
-    prelude::*,
-    na::Vector2,
-};
+use live::save_load::{load_settings, SettingsSource, SettingsWatcher};
+use live::simulation::Simulation;
+use live::snapshot::{load_snapshot, save_snapshot, Autosave, DEFAULT_AUTOSAVE_DIR, DEFAULT_SNAPSHOT_FILE};
 
 use winapi::shared::windef::HWND;
 use winapi::shared::basetsd::LONG_PTR;
@@ -30,6 +26,7 @@ use winapi::um::winuser::{
     MSG,
 
     SetProcessDPIAware,
+    GetSystemMetrics,
     DefWindowProcW,
     PostQuitMessage,
     DestroyWindow,
@@ -45,56 +42,95 @@ use winapi::um::winuser::{
     WM_NCCREATE,
     WM_PAINT,
     WM_ERASEBKGND,
+
+    SM_CXSCREEN,
+    SM_CYSCREEN,
 };
 
 use wallpaper_app::*;
-use wallpaper_app::drawing::colors::*;
 
-use live::app::*;
-use live::physics::*;
-use live::graphics::*;
-use live::bacteries_processing::*;
+use graphics::*;
 
-pub mod live;
+pub mod graphics;
+
+/// Cleared on window close or Ctrl+C, both loops finish and the final snapshot is written.
+static RUNNING: AtomicBool = AtomicBool::new(true);
 
 fn main() {
     unsafe { SetProcessDPIAware(); }
-    build_app();
+    let width = unsafe { GetSystemMetrics(SM_CXSCREEN) } as usize;
+    let height = unsafe { GetSystemMetrics(SM_CYSCREEN) } as usize;
+    let current_dir = std::env::current_dir().unwrap();
+    let snapshot_path = current_dir.join(DEFAULT_SNAPSHOT_FILE);
+    let autosave_dir = current_dir.join(DEFAULT_AUTOSAVE_DIR);
+    let simulation = Arc::new(Mutex::new(create_simulation(width, height, &snapshot_path, &autosave_dir)));
+
+    if let Err(e) = ctrlc::set_handler(|| RUNNING.store(false, Ordering::SeqCst)) {
+        eprintln!("Can`t set the stop signal handler with error: {}", e);
+    }
+
     let window_handle = create_desktop_window_fast("Live", Some(window_procedure));
+    attach_simulation(window_handle, simulation.clone());
     let delay = 1_000_000 / 80;
 
-    loop_logic(delay);
-    loop_graphics(delay, &mut mut_app_data(), window_handle);
+    let logic = loop_logic(simulation.clone(), &autosave_dir);
+    loop_graphics(delay, window_handle);
+
+    if logic.join().is_err() {
+        eprintln!("Logic thread panicked, the final snapshot is not written.");
+        return;
+    }
+    let simulation = simulation.lock().unwrap();
+    match save_snapshot(&simulation, &snapshot_path) {
+        Ok(_) => eprintln!("Saved {}", snapshot_path.display()),
+        Err(e) => eprintln!("Can`t save snapshot {} with error: {}", snapshot_path.display(), e),
+    }
 }
 
-fn build_app() {
-    unsafe { APP_DATA = AppData::lazy() };
-    let app_mutex = mut_app_data();
-    let mut app = app_mutex.lock().unwrap();
-    app.live_data.settings = load_settings();
-    app.frames_in_day = app.live_data.settings.day_length_sec / app.delta_time;
-    app.build_physics();
-    let radius = app.live_data.settings.radius_range.clone();
-    app.spawn_bacteries(radius);
-    app.with_edges(100.0, 100.0);
-
-    for i in app.live_data.bacteries.into_iter() {
-        let x = rand::thread_rng().gen_range(-100..100) as f32;
-        let y = rand::thread_rng().gen_range(-100..100) as f32;
-        let rb = app.live_data.bacteries.rigidbody[i];
-        app.live_data.physics_data.get_rb_mut(rb).set_linvel(Vector2::new(x, y), true);
+/// Resume the world from the newest of the final and autosave snapshots, or start a new one.
+fn create_simulation(width: usize, height: usize, snapshot_path: &Path, autosave_dir: &Path) -> Simulation {
+    let modified = |path: &PathBuf| path.metadata().and_then(|m| m.modified()).ok();
+    let newest = [Some(snapshot_path.to_path_buf()), Autosave::latest(autosave_dir)]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.exists())
+        .max_by_key(modified);
+
+    if let Some(path) = newest {
+        match load_snapshot(&path) {
+            Ok(mut simulation) => {
+                eprintln!("Resumed from {} at tick {}", path.display(), simulation.frame_num());
+                // The settings file may have been edited while the wallpaper was closed.
+                SettingsWatcher::new(SettingsSource::new(None, Vec::new())).apply_now(&mut simulation);
+                return simulation;
+            },
+            Err(e) => eprintln!("Can`t load snapshot {} with error: {}", path.display(), e),
+        }
     }
-    drop(app);
+
+    let settings = load_settings().unwrap_or_else(|errors| {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        std::process::exit(2);
+    });
+    Simulation::new(width, height, settings)
 }
 
-fn loop_graphics(delay: u64, app: &mut Mutex<AppData>, window_handle: HWND) {
+/// Store the simulation in the window user data, `window_procedure` paints it and releases on `WM_DESTROY`.
+fn attach_simulation(window_handle: HWND, simulation: Arc<Mutex<Simulation>>) {
+    let ptr = Arc::into_raw(simulation);
+    unsafe { SetWindowLongPtrW(window_handle, GWLP_USERDATA, ptr as LONG_PTR) };
+}
+
+fn loop_graphics(delay: u64, window_handle: HWND) {
     let msg = MSG::default();
     let graphics_pipeline = GraphicsPipeline::new(handle_window_messages);
 
-    loop { // ToDo: stop on app close
+    while RUNNING.load(Ordering::SeqCst) {
         let frame_start = std::time::Instant::now();
 
-        if graphics_pipeline.step(msg, app, window_handle) {
+        if graphics_pipeline.step(msg, window_handle) {
             let elapsed = frame_start.elapsed().as_micros();
             if (elapsed as u64) < delay {
                 std::thread::sleep(std::time::Duration::from_micros(delay - elapsed as u64));
@@ -103,47 +139,43 @@ fn loop_graphics(delay: u64, app: &mut Mutex<AppData>, window_handle: HWND) {
     }
 }
 
-fn loop_logic(delay: u64) {
+fn loop_logic(simulation: Arc<Mutex<Simulation>>, autosave_dir: &Path) -> JoinHandle<()> {
+    let mut autosave = Autosave::new(autosave_dir)
+        .map_err(|e| eprintln!("Can`t create autosave directory {} with error: {}", autosave_dir.display(), e))
+        .ok();
+
+    let mut settings_watcher = SettingsWatcher::new(SettingsSource::new(None, Vec::new()));
+
     std::thread::spawn(move || {
-        let mut physics_pipeline = PhysicsPipeline::new();
-        loop {
-            let mut app = mut_app_data().lock().unwrap();
-            let frame_start = std::time::Instant::now();
-
-            app.day_progress = (app.frame_num as f32 % app.frames_in_day / app.frames_in_day).clamp(0.0, 1.0);
-            app.live_data.light_force = interpolate_floats(&app.live_data.settings.light_force, app.day_progress);
-
-            if app.frame_num % 100 == 0 {
-                let pos = rand_range_vec2(0.0..app.width as f32, 0.0..app.height as f32);
-                let radius = app.live_data.settings.radius_range.clone();
-                app.live_data.spawn_bac(pos, rand_ranged_i32(radius));
+        let mut last_advance = std::time::Instant::now();
+        while RUNNING.load(Ordering::SeqCst) {
+            let mut simulation = simulation.lock().unwrap();
+            let now = std::time::Instant::now();
+
+            simulation.advance(now - last_advance);
+            last_advance = now;
+//            live::save_load::try_save(simulation.app());
+            if let Some(Err(e)) = autosave.as_mut().map(|a| a.tick(&simulation)) {
+                eprintln!("Can`t autosave with error: {}", e);
             }
+            settings_watcher.update(&mut simulation);
+            let sleep = simulation.next_step_in();
+            drop(simulation);
 
-            physics_step(&mut physics_pipeline, &mut app.live_data.physics_data);
-            process_bacteries(&mut app);
-//            try_save(&app);
-            drop(app);
-
-            let elapsed = frame_start.elapsed().as_micros();
-            if (elapsed as u64) < delay {
-                std::thread::sleep(std::time::Duration::from_micros(delay - elapsed as u64));
+            if !sleep.is_zero() {
+                std::thread::sleep(sleep);
             }
         }
-    });
+    })
 }
 
-fn simulate_frame(hwnd: HWND, app: &mut Mutex<AppData>) {
-    let mut app = app.lock().unwrap();
-    if app.frame_processed { return }
-    app.frame_processed = true;
+fn paint_window(hwnd: HWND, simulation: &Mutex<Simulation>) {
+    let simulation = simulation.lock().unwrap();
 
     let mut ps: PAINTSTRUCT = PAINTSTRUCT::default();
     let hdc = unsafe { BeginPaint(hwnd, &mut ps) };
-    paint_frame(hdc, &ps, &mut app);
+    paint_frame(hdc, &ps, simulation.app());
     unsafe { EndPaint(hwnd, &ps) };
-
-    app.frame_num += 1;
-    app.frame_processed = false;
 }
 
 pub unsafe extern "system" fn window_procedure(hwnd: HWND, msg: UINT, w_param: WPARAM, l_param: LPARAM,) -> LRESULT {
@@ -154,20 +186,32 @@ pub unsafe extern "system" fn window_procedure(hwnd: HWND, msg: UINT, w_param: W
             if createstruct.is_null() {
                 return 0;
             }
-            let boxed_i32_ptr = (*createstruct).lpCreateParams;
-            SetWindowLongPtrW(hwnd, GWLP_USERDATA, boxed_i32_ptr as LONG_PTR);
+            // `wallpaper_app` passes a boxed placeholder, the simulation is attached after creation.
+            let boxed_i32_ptr = (*createstruct).lpCreateParams as *mut i32;
+            if !boxed_i32_ptr.is_null() {
+                drop(Box::from_raw(boxed_i32_ptr));
+            }
             return 1;
         }
         WM_CREATE => println!("WM Create"),
         WM_CLOSE => drop(DestroyWindow(hwnd)),
         WM_DESTROY => {
-            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut i32;
-            drop(Box::from_raw(ptr));
-            println!("Cleaned up the box.");
+            let ptr = SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0) as *const Mutex<Simulation>;
+            if !ptr.is_null() {
+                drop(Arc::from_raw(ptr));
+            }
+            println!("Released the simulation.");
+            RUNNING.store(false, Ordering::SeqCst);
             PostQuitMessage(0);
         }
         WM_ERASEBKGND => return 1,
-        WM_PAINT => simulate_frame(hwnd, mut_app_data()),
+        WM_PAINT => {
+            let ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const Mutex<Simulation>;
+            if ptr.is_null() {
+                return DefWindowProcW(hwnd, msg, w_param, l_param);
+            }
+            paint_window(hwnd, &*ptr);
+        }
         _ => return DefWindowProcW(hwnd, msg, w_param, l_param),
     }
 